@@ -261,6 +261,10 @@ pub mod fs {
         ///
         /// It will delete the original file if `old` already exists.
         pub fn ax_rename(old: &str, new: &str) -> AxResult;
+        /// Creates a symbolic link at `path` which points to `target`.
+        pub fn ax_symlink(target: &str, path: &str) -> AxResult;
+        /// Reads the target of the symbolic link at `path`.
+        pub fn ax_read_link(path: &str) -> AxResult<alloc::string::String>;
 
         /// Returns the current working directory.
         pub fn ax_current_dir() -> AxResult<alloc::string::String>;
@@ -11,6 +11,21 @@ use crate::ctypes;
 
 pub const AX_FILE_LIMIT: usize = 1024;
 
+/// The file status flags that can be changed by `F_SETFL`.
+const SETFL_MASK: u32 = ctypes::O_APPEND | ctypes::O_NONBLOCK;
+
+/// The open flags (access mode and status flags) of each fd, as returned by
+/// `F_GETFL`.
+static FD_STATUS_FLAGS: RwLock<[u32; AX_FILE_LIMIT]> = RwLock::new(stdio_status_flags());
+
+const fn stdio_status_flags() -> [u32; AX_FILE_LIMIT] {
+    let mut flags = [0; AX_FILE_LIMIT];
+    flags[0] = ctypes::O_RDONLY;
+    flags[1] = ctypes::O_WRONLY;
+    flags[2] = ctypes::O_WRONLY;
+    flags
+}
+
 #[allow(dead_code)]
 pub trait FileLike: Send + Sync {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize>;
@@ -48,10 +63,23 @@ pub fn close_file_like(fd: c_int) -> LinuxResult {
         .write()
         .remove(fd as usize)
         .ok_or(LinuxError::EBADF)?;
+    set_status_flags(fd, 0);
     drop(f);
     Ok(())
 }
 
+/// Returns the open flags of `fd`, see [`set_status_flags`].
+pub fn status_flags(fd: c_int) -> u32 {
+    FD_STATUS_FLAGS.read()[fd as usize]
+}
+
+/// Records the open flags of `fd`, which are reported by `F_GETFL`.
+///
+/// Only the access mode, `O_APPEND` and `O_NONBLOCK` are kept.
+pub fn set_status_flags(fd: c_int, flags: u32) {
+    FD_STATUS_FLAGS.write()[fd as usize] = flags & (ctypes::O_ACCMODE | SETFL_MASK);
+}
+
 /// Close a file by `fd`.
 pub fn sys_close(fd: c_int) -> c_int {
     debug!("sys_close <= {}", fd);
@@ -64,9 +92,26 @@ pub fn sys_close(fd: c_int) -> c_int {
 fn dup_fd(old_fd: c_int) -> LinuxResult<c_int> {
     let f = get_file_like(old_fd)?;
     let new_fd = add_file_like(f)?;
+    set_status_flags(new_fd, status_flags(old_fd));
     Ok(new_fd)
 }
 
+/// Duplicates `old_fd` to the lowest free fd that is not less than `min_fd`.
+fn dup_fd_from(old_fd: c_int, min_fd: usize) -> LinuxResult<c_int> {
+    if min_fd >= AX_FILE_LIMIT {
+        return Err(LinuxError::EINVAL);
+    }
+    let f = get_file_like(old_fd)?;
+    let mut fd_table = FD_TABLE.write();
+    let new_fd = (min_fd..AX_FILE_LIMIT)
+        .find(|&fd| !fd_table.is_assigned(fd))
+        .ok_or(LinuxError::EMFILE)?;
+    fd_table.add_at(new_fd, f).ok_or(LinuxError::EMFILE)?;
+    drop(fd_table);
+    set_status_flags(new_fd as c_int, status_flags(old_fd));
+    Ok(new_fd as c_int)
+}
+
 /// Duplicate a file descriptor.
 pub fn sys_dup(old_fd: c_int) -> c_int {
     debug!("sys_dup <= {}", old_fd);
@@ -75,7 +120,7 @@ pub fn sys_dup(old_fd: c_int) -> c_int {
 
 /// Duplicate a file descriptor, but it uses the file descriptor number specified in `new_fd`.
 ///
-/// TODO: `dup2` should forcibly close new_fd if it is already opened.
+/// If `new_fd` is already opened, it is closed first.
 pub fn sys_dup2(old_fd: c_int, new_fd: c_int) -> c_int {
     debug!("sys_dup2 <= old_fd: {}, new_fd: {}", old_fd, new_fd);
     syscall_body!(sys_dup2, {
@@ -92,10 +137,13 @@ pub fn sys_dup2(old_fd: c_int, new_fd: c_int) -> c_int {
         }
 
         let f = get_file_like(old_fd)?;
-        FD_TABLE
-            .write()
+        let mut fd_table = FD_TABLE.write();
+        fd_table.remove(new_fd as usize);
+        fd_table
             .add_at(new_fd as usize, f)
             .ok_or(LinuxError::EMFILE)?;
+        drop(fd_table);
+        set_status_flags(new_fd, status_flags(old_fd));
 
         Ok(new_fd)
     })
@@ -103,26 +151,43 @@ pub fn sys_dup2(old_fd: c_int, new_fd: c_int) -> c_int {
 
 /// Manipulate file descriptor.
 ///
-/// TODO: `SET/GET` command is ignored, hard-code stdin/stdout
+/// Supports `F_DUPFD`, `F_DUPFD_CLOEXEC`, `F_GETFL` and `F_SETFL`. Only
+/// `O_APPEND` and `O_NONBLOCK` can be changed by `F_SETFL`. `F_GETFD` and
+/// `F_SETFD` are accepted but fd flags (`FD_CLOEXEC`) are not tracked.
 pub fn sys_fcntl(fd: c_int, cmd: c_int, arg: usize) -> c_int {
     debug!("sys_fcntl <= fd: {} cmd: {} arg: {}", fd, cmd, arg);
     syscall_body!(sys_fcntl, {
         match cmd as u32 {
-            ctypes::F_DUPFD => dup_fd(fd),
+            ctypes::F_DUPFD => dup_fd_from(fd, arg),
             ctypes::F_DUPFD_CLOEXEC => {
                 // TODO: Change fd flags
-                dup_fd(fd)
+                dup_fd_from(fd, arg)
+            }
+            ctypes::F_GETFD | ctypes::F_SETFD => {
+                get_file_like(fd)?;
+                Ok(0)
+            }
+            ctypes::F_GETFL => {
+                get_file_like(fd)?;
+                Ok(status_flags(fd) as c_int)
             }
             ctypes::F_SETFL => {
-                if fd == 0 || fd == 1 || fd == 2 {
-                    return Ok(0);
+                let f = get_file_like(fd)?;
+                let arg = arg as u32;
+                let flags = (status_flags(fd) & !SETFL_MASK) | (arg & SETFL_MASK);
+                if !(0..=2).contains(&fd) {
+                    f.set_nonblocking(arg & ctypes::O_NONBLOCK != 0)?;
                 }
-                get_file_like(fd)?.set_nonblocking(arg & (ctypes::O_NONBLOCK as usize) > 0)?;
+                #[cfg(feature = "fs")]
+                if let Ok(file) = f.into_any().downcast::<super::fs::File>() {
+                    file.set_append(arg & ctypes::O_APPEND != 0);
+                }
+                set_status_flags(fd, flags);
                 Ok(0)
             }
             _ => {
                 warn!("unsupported fcntl parameters: cmd {}", cmd);
-                Ok(0)
+                Err(LinuxError::EINVAL)
             }
         }
     })
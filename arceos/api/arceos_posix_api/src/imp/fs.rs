@@ -1,7 +1,8 @@
+use alloc::string::String;
 use alloc::sync::Arc;
-use core::ffi::{c_char, c_int};
+use core::ffi::{c_char, c_int, c_void};
 
-use axerrno::{LinuxError, LinuxResult};
+use axerrno::{AxError, LinuxError, LinuxResult};
 use axfs::fops::OpenOptions;
 use axio::{PollState, SeekFrom};
 use axsync::Mutex;
@@ -11,15 +12,22 @@ use crate::{ctypes, utils::char_ptr_to_str};
 
 pub struct File {
     inner: Mutex<axfs::fops::File>,
+    path: String,
 }
 
 impl File {
-    fn new(inner: axfs::fops::File) -> Self {
+    fn new(inner: axfs::fops::File, path: &str) -> Self {
         Self {
             inner: Mutex::new(inner),
+            path: path.into(),
         }
     }
 
+    /// Returns the path that the file was opened with.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
     fn add_to_fd_table(self) -> LinuxResult<c_int> {
         super::fd_ops::add_file_like(Arc::new(self))
     }
@@ -30,15 +38,63 @@ impl File {
             .downcast::<Self>()
             .map_err(|_| LinuxError::EINVAL)
     }
+
+    /// Reads the file at the given position, without changing the file
+    /// position.
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> LinuxResult<usize> {
+        Ok(self.inner.lock().read_at(offset, buf)?)
+    }
+
+    /// Writes the file at the given position, without changing the file
+    /// position.
+    pub fn write_at(&self, offset: u64, buf: &[u8]) -> LinuxResult<usize> {
+        Ok(self.inner.lock().write_at(offset, buf)?)
+    }
+
+    /// Reads directory entries starting from the `start_idx`-th one, if the
+    /// file is a directory, without changing the file position.
+    pub fn read_dir_at(
+        &self,
+        start_idx: usize,
+        dirents: &mut [axfs::fops::DirEntry],
+    ) -> LinuxResult<usize> {
+        Ok(self.inner.lock().read_dir_at(start_idx, dirents)?)
+    }
+
+    /// Moves the file position, returning the new position.
+    pub fn seek(&self, pos: SeekFrom) -> LinuxResult<u64> {
+        Ok(self.inner.lock().seek(pos)?)
+    }
+
+    /// Sets whether writes go to the end of the file (`O_APPEND`).
+    pub fn set_append(&self, append: bool) {
+        self.inner.lock().set_append(append);
+    }
 }
 
 impl FileLike for File {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        Ok(self.inner.lock().read(buf)?)
+        loop {
+            let res = self.inner.lock().read(buf);
+            match res {
+                // a FIFO waiting for its writer
+                Err(AxError::WouldBlock) => crate::sys_sched_yield(),
+                res => return Ok(res?),
+            };
+        }
     }
 
     fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
-        Ok(self.inner.lock().write(buf)?)
+        loop {
+            let res = self.inner.lock().write(buf);
+            match res {
+                // a FIFO waiting for its reader to make room
+                Err(AxError::WouldBlock) => crate::sys_sched_yield(),
+                // a FIFO without readers
+                Err(AxError::NotConnected) => return Err(LinuxError::EPIPE),
+                res => return Ok(res?),
+            };
+        }
     }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
@@ -94,10 +150,12 @@ fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
         options.truncate(true);
     }
     if flags & ctypes::O_CREAT != 0 {
-        options.create(true);
-    }
-    if flags & ctypes::O_EXEC != 0 {
-        options.create_new(true);
+        // O_EXCL only has a meaning together with O_CREAT
+        if flags & ctypes::O_EXCL != 0 {
+            options.create_new(true);
+        } else {
+            options.create(true);
+        }
     }
     options
 }
@@ -111,8 +169,11 @@ pub fn sys_open(filename: *const c_char, flags: c_int, mode: ctypes::mode_t) ->
     debug!("sys_open <= {:?} {:#o} {:#o}", filename, flags, mode);
     syscall_body!(sys_open, {
         let options = flags_to_options(flags, mode);
-        let file = axfs::fops::File::open(filename?, &options)?;
-        File::new(file).add_to_fd_table()
+        let filename = filename?;
+        let file = axfs::fops::File::open(filename, &options)?;
+        let fd = File::new(file, filename).add_to_fd_table()?;
+        super::fd_ops::set_status_flags(fd, flags as u32);
+        Ok(fd)
     })
 }
 
@@ -128,11 +189,77 @@ pub fn sys_lseek(fd: c_int, offset: ctypes::off_t, whence: c_int) -> ctypes::off
             2 => SeekFrom::End(offset as _),
             _ => return Err(LinuxError::EINVAL),
         };
-        let off = File::from_fd(fd)?.inner.lock().seek(pos)?;
+        let off = File::from_fd(fd)?.seek(pos)?;
         Ok(off)
     })
 }
 
+/// Read data from the file indicated by `fd` at the given `offset`.
+///
+/// The file position is not changed. Return the read size if success.
+pub fn sys_pread64(
+    fd: c_int,
+    buf: *mut c_void,
+    count: usize,
+    offset: ctypes::off_t,
+) -> ctypes::ssize_t {
+    debug!("sys_pread64 <= {} {:#x} {} {}", fd, buf as usize, count, offset);
+    syscall_body!(sys_pread64, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        if offset < 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let dst = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, count) };
+        let file = get_file_like(fd)?
+            .into_any()
+            .downcast::<File>()
+            .map_err(|_| LinuxError::ESPIPE)?;
+        Ok(file.read_at(offset as _, dst)? as ctypes::ssize_t)
+    })
+}
+
+/// Write data to the file indicated by `fd` at the given `offset`.
+///
+/// The file position is not changed. Return the written size if success.
+pub fn sys_pwrite64(
+    fd: c_int,
+    buf: *const c_void,
+    count: usize,
+    offset: ctypes::off_t,
+) -> ctypes::ssize_t {
+    debug!("sys_pwrite64 <= {} {:#x} {} {}", fd, buf as usize, count, offset);
+    syscall_body!(sys_pwrite64, {
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        if offset < 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let src = unsafe { core::slice::from_raw_parts(buf as *const u8, count) };
+        let file = get_file_like(fd)?
+            .into_any()
+            .downcast::<File>()
+            .map_err(|_| LinuxError::ESPIPE)?;
+        Ok(file.write_at(offset as _, src)? as ctypes::ssize_t)
+    })
+}
+
+/// Truncate or zero-extend the file indicated by `fd` to `length` bytes.
+///
+/// Return 0 if success.
+pub fn sys_ftruncate(fd: c_int, length: ctypes::off_t) -> c_int {
+    debug!("sys_ftruncate <= {} {}", fd, length);
+    syscall_body!(sys_ftruncate, {
+        if length < 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        File::from_fd(fd)?.inner.lock().truncate(length as _)?;
+        Ok(0)
+    })
+}
+
 /// Get the file metadata by `path` and write into `buf`.
 ///
 /// Return 0 if success.
@@ -145,8 +272,9 @@ pub unsafe fn sys_stat(path: *const c_char, buf: *mut ctypes::stat) -> c_int {
         }
         let mut options = OpenOptions::new();
         options.read(true);
-        let file = axfs::fops::File::open(path?, &options)?;
-        let st = File::new(file).stat()?;
+        let path = path?;
+        let file = axfs::fops::File::open(path, &options)?;
+        let st = File::new(file, path).stat()?;
         unsafe { *buf = st };
         Ok(0)
     })
@@ -1,11 +1,12 @@
 use alloc::sync::Arc;
 use core::ffi::c_int;
+use core::sync::atomic::{AtomicBool, Ordering};
 
 use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
 use axsync::Mutex;
 
-use super::fd_ops::{add_file_like, close_file_like, FileLike};
+use super::fd_ops::{add_file_like, close_file_like, set_status_flags, FileLike};
 use crate::ctypes;
 
 #[derive(Copy, Clone, PartialEq)]
@@ -77,6 +78,7 @@ impl PipeRingBuffer {
 pub struct Pipe {
     readable: bool,
     buffer: Arc<Mutex<PipeRingBuffer>>,
+    nonblocking: AtomicBool,
 }
 
 impl Pipe {
@@ -85,10 +87,12 @@ impl Pipe {
         let read_end = Pipe {
             readable: true,
             buffer: buffer.clone(),
+            nonblocking: AtomicBool::new(false),
         };
         let write_end = Pipe {
             readable: false,
             buffer,
+            nonblocking: AtomicBool::new(false),
         };
         (read_end, write_end)
     }
@@ -104,6 +108,10 @@ impl Pipe {
     pub fn write_end_close(&self) -> bool {
         Arc::strong_count(&self.buffer) == 1
     }
+
+    fn is_nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
 }
 
 impl FileLike for Pipe {
@@ -117,9 +125,13 @@ impl FileLike for Pipe {
             let mut ring_buffer = self.buffer.lock();
             let loop_read = ring_buffer.available_read();
             if loop_read == 0 {
-                if self.write_end_close() {
+                // return what has been read instead of waiting for more
+                if read_size > 0 || self.write_end_close() {
                     return Ok(read_size);
                 }
+                if self.is_nonblocking() {
+                    return Err(LinuxError::EAGAIN);
+                }
                 drop(ring_buffer);
                 // Data not ready, wait for write end
                 crate::sys_sched_yield(); // TODO: use synconize primitive
@@ -142,9 +154,20 @@ impl FileLike for Pipe {
         let mut write_size = 0usize;
         let max_len = buf.len();
         loop {
+            // the read end has been closed
+            if Arc::strong_count(&self.buffer) == 1 {
+                return Err(LinuxError::EPIPE);
+            }
             let mut ring_buffer = self.buffer.lock();
             let loop_write = ring_buffer.available_write();
             if loop_write == 0 {
+                if self.is_nonblocking() {
+                    return if write_size > 0 {
+                        Ok(write_size)
+                    } else {
+                        Err(LinuxError::EAGAIN)
+                    };
+                }
                 drop(ring_buffer);
                 // Buffer is full, wait for read end to consume
                 crate::sys_sched_yield(); // TODO: use synconize primitive
@@ -185,7 +208,8 @@ impl FileLike for Pipe {
         })
     }
 
-    fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
+    fn set_nonblocking(&self, nonblocking: bool) -> LinuxResult {
+        self.nonblocking.store(nonblocking, Ordering::Relaxed);
         Ok(())
     }
 }
@@ -205,6 +229,43 @@ pub fn sys_pipe(fds: &mut [c_int]) -> c_int {
         let write_fd = add_file_like(Arc::new(write_end)).inspect_err(|_| {
             close_file_like(read_fd).ok();
         })?;
+        set_status_flags(read_fd, ctypes::O_RDONLY);
+        set_status_flags(write_fd, ctypes::O_WRONLY);
+
+        fds[0] = read_fd as c_int;
+        fds[1] = write_fd as c_int;
+
+        Ok(0)
+    })
+}
+
+/// Create a pipe with flags
+///
+/// `O_NONBLOCK` makes both ends non-blocking. `O_CLOEXEC` is accepted and
+/// ignored, since there is no `exec`.
+///
+/// Return 0 if succeed
+pub fn sys_pipe2(fds: &mut [c_int], flags: c_int) -> c_int {
+    debug!("sys_pipe2 <= {:#x} {:#x}", fds.as_ptr() as usize, flags);
+    syscall_body!(sys_pipe2, {
+        if fds.len() != 2 {
+            return Err(LinuxError::EFAULT);
+        }
+        if flags & !((ctypes::O_NONBLOCK | ctypes::O_CLOEXEC) as c_int) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+
+        let (read_end, write_end) = Pipe::new();
+        let nonblocking = flags & ctypes::O_NONBLOCK as c_int != 0;
+        read_end.set_nonblocking(nonblocking)?;
+        write_end.set_nonblocking(nonblocking)?;
+        let read_fd = add_file_like(Arc::new(read_end))?;
+        let write_fd = add_file_like(Arc::new(write_end)).inspect_err(|_| {
+            close_file_like(read_fd).ok();
+        })?;
+        let status = flags as u32 & ctypes::O_NONBLOCK;
+        set_status_flags(read_fd, ctypes::O_RDONLY | status);
+        set_status_flags(write_fd, ctypes::O_WRONLY | status);
 
         fds[0] = read_fd as c_int;
         fds[1] = write_fd as c_int;
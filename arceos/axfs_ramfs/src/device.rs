@@ -0,0 +1,84 @@
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType};
+use axfs_vfs::VfsResult;
+
+/// The callback to read from a device at the given offset.
+pub type DeviceReadFn = fn(offset: u64, buf: &mut [u8]) -> VfsResult<usize>;
+
+/// The callback to write to a device at the given offset.
+pub type DeviceWriteFn = fn(offset: u64, buf: &[u8]) -> VfsResult<usize>;
+
+/// The character device node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`], forwarding reads and writes to the
+/// callbacks it is created with.
+pub struct DeviceNode {
+    major: u32,
+    minor: u32,
+    read: DeviceReadFn,
+    write: DeviceWriteFn,
+}
+
+impl DeviceNode {
+    /// Creates a character device with the given device number and callbacks.
+    pub const fn new(major: u32, minor: u32, read: DeviceReadFn, write: DeviceWriteFn) -> Self {
+        Self {
+            major,
+            minor,
+            read,
+            write,
+        }
+    }
+
+    /// Creates a null device (like `/dev/null`, 1:3), which discards all
+    /// writes and returns EOF on reads.
+    pub const fn null() -> Self {
+        Self::new(1, 3, null_read, null_write)
+    }
+
+    /// Returns the major device number.
+    pub const fn major(&self) -> u32 {
+        self.major
+    }
+
+    /// Returns the minor device number.
+    pub const fn minor(&self) -> u32 {
+        self.minor
+    }
+}
+
+impl VfsNodeOps for DeviceNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_file(),
+            VfsNodeType::CharDevice,
+            0,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        (self.read)(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        (self.write)(offset, buf)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    impl_vfs_non_dir_default! {}
+}
+
+fn null_read(_offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
+    Ok(0)
+}
+
+fn null_write(_offset: u64, buf: &[u8]) -> VfsResult<usize> {
+    Ok(buf.len())
+}
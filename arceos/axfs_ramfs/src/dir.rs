@@ -1,13 +1,18 @@
 use alloc::collections::BTreeMap;
 use alloc::sync::{Arc, Weak};
-use alloc::{string::String, vec::Vec};
+use alloc::{format, string::String, vec::Vec};
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType};
 use axfs_vfs::{VfsError, VfsResult};
 use log::warn;
-use spin::RwLock;
+use spin::{Mutex, RwLock};
 
+use crate::device::DeviceNode;
+use crate::fifo::FifoNode;
 use crate::file::FileNode;
+use crate::symlink::SymlinkNode;
+use crate::{memory_grow, memory_shrink};
 
 /// The directory node in the RAM filesystem.
 ///
@@ -16,17 +21,117 @@ pub struct DirNode {
     this: Weak<DirNode>,
     parent: RwLock<Weak<dyn VfsNodeOps>>,
     children: RwLock<BTreeMap<String, VfsNodeRef>>,
+    /// Cached attributes, cleared under the `children` write lock whenever
+    /// an entry is added or removed.
+    attr: Mutex<Option<VfsNodeAttr>>,
+    /// Advanced under the `children` write lock whenever an entry is added,
+    /// removed or renamed.
+    generation: AtomicU64,
+    /// Roots of the trees mounted on child directories, by the stored name.
+    mounts: RwLock<BTreeMap<String, VfsNodeRef>>,
+    case_insensitive: bool,
 }
 
 impl DirNode {
-    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>) -> Arc<Self> {
+    /// Creates a new directory node. If `case_insensitive` is set, names are
+    /// looked up ignoring ASCII case, and so are all subdirectories created
+    /// in it.
+    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>, case_insensitive: bool) -> Arc<Self> {
         Arc::new_cyclic(|this| Self {
             this: this.clone(),
             parent: RwLock::new(parent.unwrap_or_else(|| Weak::<Self>::new())),
             children: RwLock::new(BTreeMap::new()),
+            attr: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            mounts: RwLock::new(BTreeMap::new()),
+            case_insensitive,
         })
     }
 
+    /// Checks whether names in this directory are looked up ignoring ASCII case.
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Finds the entry matching `name`, ignoring ASCII case if enabled.
+    ///
+    /// Returns the stored name along with the node, which differs from
+    /// `name` when it only matches after case folding.
+    fn find<'a>(
+        &self,
+        children: &'a BTreeMap<String, VfsNodeRef>,
+        name: &str,
+    ) -> Option<(&'a String, &'a VfsNodeRef)> {
+        children.get_key_value(name).or_else(|| {
+            if self.case_insensitive {
+                children.iter().find(|(k, _)| k.eq_ignore_ascii_case(name))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the child with the given name, or the root mounted on it.
+    fn child(&self, name: &str) -> Option<VfsNodeRef> {
+        let children = self.children.read();
+        let (key, node) = self.find(&children, name)?;
+        Some(self.mounts.read().get(key).unwrap_or(node).clone())
+    }
+
+    /// Mounts `root` on the child directory `name`, so that lookups through
+    /// `name` continue in the mounted tree until it is unmounted.
+    ///
+    /// The directory itself is kept but hidden. If `root` is a [`DirNode`],
+    /// its `..` is set to this directory; other filesystems should do the
+    /// same in their [`mount`](axfs_vfs::VfsOps::mount).
+    pub fn mount(&self, name: &str, root: VfsNodeRef) -> VfsResult {
+        let children = self.children.read();
+        let (key, node) = self.find(&children, name).ok_or(VfsError::NotFound)?;
+        if !node.get_attr()?.is_dir() {
+            return Err(VfsError::NotADirectory);
+        }
+        let mut mounts = self.mounts.write();
+        if mounts.contains_key(key) {
+            return Err(VfsError::ResourceBusy);
+        }
+        if let Some(dir) = root.as_any().downcast_ref::<DirNode>() {
+            dir.set_parent(self.this.upgrade().map(|this| this as VfsNodeRef).as_ref());
+        }
+        mounts.insert(key.clone(), root);
+        Ok(())
+    }
+
+    /// Unmounts the tree mounted on `name`, and returns its root.
+    pub fn unmount(&self, name: &str) -> VfsResult<VfsNodeRef> {
+        let children = self.children.read();
+        let (key, _) = self.find(&children, name).ok_or(VfsError::NotFound)?;
+        self.mounts.write().remove(key).ok_or(VfsError::InvalidInput)
+    }
+
+    /// Checks whether a tree is mounted on the child `key`.
+    fn is_mountpoint(&self, key: &str) -> bool {
+        self.mounts.read().contains_key(key)
+    }
+
+    /// Records that the entries have changed: drops the cached attributes
+    /// and advances the generation. Must be called with the `children` write
+    /// lock held.
+    fn entries_changed(&self) {
+        *self.attr.lock() = None;
+        self.generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns the generation of this directory, which advances every time an
+    /// entry is created, removed or renamed.
+    ///
+    /// A reader that lists the directory in several
+    /// [`read_dir`](VfsNodeOps::read_dir) calls can compare the generation
+    /// before and after to detect that entries may have been skipped or
+    /// repeated, and restart.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
     pub(super) fn set_parent(&self, parent: Option<&VfsNodeRef>) {
         *self.parent.write() = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
     }
@@ -36,56 +141,184 @@ impl DirNode {
         self.children.read().keys().cloned().collect()
     }
 
+    /// Returns all entries in this directory as `(name, node)` pairs.
+    ///
+    /// The pairs are collected under a single lock, so they are consistent
+    /// with each other even if the directory is modified concurrently.
+    pub fn entries(&self) -> Vec<(String, VfsNodeRef)> {
+        self.children
+            .read()
+            .iter()
+            .map(|(name, node)| (name.clone(), node.clone()))
+            .collect()
+    }
+
+    /// Returns the number of entries in this directory, excluding `.` and `..`.
+    pub fn len(&self) -> usize {
+        self.children.read().len()
+    }
+
+    /// Checks whether this directory has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.children.read().is_empty()
+    }
+
     /// Checks whether a node with the given name exists in this directory.
+    ///
+    /// In a case-insensitive directory, names differing only in ASCII case
+    /// are considered the same.
     pub fn exist(&self, name: &str) -> bool {
-        self.children.read().contains_key(name)
+        self.find(&self.children.read(), name).is_some()
     }
 
     /// Creates a new node with the given name and type in this directory.
+    ///
+    /// The name is stored as given, even in a case-insensitive directory.
+    /// A [`VfsNodeType::CharDevice`] is created as a [null device](DeviceNode::null),
+    /// use [`add_device`](Self::add_device) for other devices.
     pub fn create_node(&self, name: &str, ty: VfsNodeType) -> VfsResult {
-        if self.exist(name) {
+        self.add_node(name, self.new_node(ty)?)
+    }
+
+    /// Adds a character device with the given name in this directory.
+    pub fn add_device(&self, name: &str, device: DeviceNode) -> VfsResult {
+        self.add_node(name, Arc::new(device))
+    }
+
+    /// Creates a symbolic link with the given name in this directory, which
+    /// points to `target`.
+    pub fn create_symlink(&self, name: &str, target: &str) -> VfsResult {
+        if target.is_empty() {
+            return Err(VfsError::NotFound);
+        }
+        self.add_node(name, Arc::new(SymlinkNode::new(target)))
+    }
+
+    fn add_node(&self, name: &str, node: VfsNodeRef) -> VfsResult {
+        let mut children = self.children.write();
+        if self.find(&children, name).is_some() {
             log::error!("AlreadyExists {}", name);
             return Err(VfsError::AlreadyExists);
         }
-        let node: VfsNodeRef = match ty {
+        children.insert(name.into(), node);
+        self.entries_changed();
+        memory_grow(name.len());
+        Ok(())
+    }
+
+    /// Creates a node of the given type, to be added in this directory.
+    fn new_node(&self, ty: VfsNodeType) -> VfsResult<VfsNodeRef> {
+        Ok(match ty {
             VfsNodeType::File => Arc::new(FileNode::new()),
-            VfsNodeType::Dir => Self::new(Some(self.this.clone())),
+            VfsNodeType::Dir => Self::new(Some(self.this.clone()), self.case_insensitive),
+            VfsNodeType::Fifo => Arc::new(FifoNode::new()),
+            VfsNodeType::CharDevice => Arc::new(DeviceNode::null()),
             _ => return Err(VfsError::Unsupported),
-        };
-        self.children.write().insert(name.into(), node);
-        Ok(())
+        })
+    }
+
+    /// Creates a node with the given type at `path`, creating all missing
+    /// intermediate directories along the way (like `mkdir -p`).
+    ///
+    /// Existing components are reused, so it succeeds if the final node
+    /// already exists with the same type.
+    pub fn create_all(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        let (name, rest) = split_path(path);
+        if let Some(rest) = rest.filter(|rest| !rest.is_empty()) {
+            let subdir = match name {
+                "" | "." => return self.create_all(rest, ty),
+                ".." => self.parent().ok_or(VfsError::NotFound)?,
+                _ => self.get_or_create(name, VfsNodeType::Dir)?,
+            };
+            subdir
+                .as_any()
+                .downcast_ref::<DirNode>()
+                .ok_or(VfsError::NotADirectory)?
+                .create_all(rest, ty)
+        } else if rest.is_some() && ty != VfsNodeType::Dir {
+            Err(VfsError::NotADirectory)
+        } else if name.is_empty() || name == "." || name == ".." {
+            Ok(()) // already exists
+        } else if self.get_or_create(name, ty)?.get_attr()?.file_type() != ty {
+            Err(VfsError::AlreadyExists)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Returns the child with the given name, or creates it with the given
+    /// type if not found.
+    fn get_or_create(&self, name: &str, ty: VfsNodeType) -> VfsResult<VfsNodeRef> {
+        let mut children = self.children.write();
+        if let Some((key, node)) = self.find(&children, name) {
+            return Ok(self.mounts.read().get(key).unwrap_or(node).clone());
+        }
+        let node = self.new_node(ty)?;
+        children.insert(name.into(), node.clone());
+        self.entries_changed();
+        memory_grow(name.len());
+        Ok(node)
     }
 
     /// Removes a node by the given name in this directory.
     pub fn remove_node(&self, name: &str) -> VfsResult {
         let mut children = self.children.write();
-        let node = children.get(name).ok_or(VfsError::NotFound)?;
+        let (key, node) = self.find(&children, name).ok_or(VfsError::NotFound)?;
+        if self.is_mountpoint(key) {
+            return Err(VfsError::ResourceBusy);
+        }
         if let Some(dir) = node.as_any().downcast_ref::<DirNode>() {
-            if !dir.children.read().is_empty() {
+            if !dir.is_empty() {
                 return Err(VfsError::DirectoryNotEmpty);
             }
         }
-        children.remove(name);
+        let key = key.clone();
+        children.remove(&key);
+        self.entries_changed();
+        memory_shrink(key.len());
         Ok(())
     }
 
     pub fn rename_node(&self, old_name: &str, new_name: &str) -> VfsResult {
         let mut children = self.children.write();
-        if !children.contains_key(old_name) {
-            return Err(VfsError::NotFound);
+        let old_key = match self.find(&children, old_name) {
+            Some((key, _)) => key.clone(),
+            None => return Err(VfsError::NotFound),
+        };
+        if self.is_mountpoint(&old_key) {
+            return Err(VfsError::ResourceBusy);
         }
-        if children.contains_key(new_name) {
+        // renaming to a different case of the same name is allowed
+        if self
+            .find(&children, new_name)
+            .is_some_and(|(key, _)| *key != old_key)
+        {
             return Err(VfsError::AlreadyExists);
         }
-        let node = children.remove(old_name).unwrap();
+        let node = children.remove(&old_key).unwrap();
         children.insert(new_name.into(), node);
+        self.entries_changed();
+        memory_shrink(old_key.len());
+        memory_grow(new_name.len());
         Ok(())
     }
 }
 
+impl Drop for DirNode {
+    fn drop(&mut self) {
+        memory_shrink(self.children.get_mut().keys().map(String::len).sum());
+    }
+}
+
 impl VfsNodeOps for DirNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_dir(4096, 0))
+        // the size of a directory is the number of its entries
+        let children = self.children.read();
+        let attr = *self
+            .attr
+            .lock()
+            .get_or_insert_with(|| VfsNodeAttr::new_dir(children.len() as _, 0));
+        Ok(attr)
     }
 
     fn parent(&self) -> Option<VfsNodeRef> {
@@ -97,12 +330,7 @@ impl VfsNodeOps for DirNode {
         let node = match name {
             "" | "." => Ok(self.clone() as VfsNodeRef),
             ".." => self.parent().ok_or(VfsError::NotFound),
-            _ => self
-                .children
-                .read()
-                .get(name)
-                .cloned()
-                .ok_or(VfsError::NotFound),
+            _ => self.child(name).ok_or(VfsError::NotFound),
         }?;
 
         if let Some(rest) = rest {
@@ -134,17 +362,21 @@ impl VfsNodeOps for DirNode {
     fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
         log::debug!("create {:?} at ramfs: {}", ty, path);
         let (name, rest) = split_path(path);
-        if let Some(rest) = rest {
+        if rest == Some("") {
+            // trailing slash: only a directory can be created
+            if ty != VfsNodeType::Dir {
+                return Err(VfsError::NotADirectory);
+            }
+            self.create(name, ty)
+        } else if let Some(rest) = rest {
             match name {
                 "" | "." => self.create(rest, ty),
                 ".." => self.parent().ok_or(VfsError::NotFound)?.create(rest, ty),
                 _ => {
-                    let subdir = self
-                        .children
-                        .read()
-                        .get(name)
-                        .ok_or(VfsError::NotFound)?
-                        .clone();
+                    let subdir = self.child(name).ok_or(VfsError::NotFound)?;
+                    if !subdir.get_attr()?.is_dir() {
+                        return Err(VfsError::NotADirectory);
+                    }
                     subdir.create(rest, ty)
                 }
             }
@@ -158,17 +390,21 @@ impl VfsNodeOps for DirNode {
     fn remove(&self, path: &str) -> VfsResult {
         log::debug!("remove at ramfs: {}", path);
         let (name, rest) = split_path(path);
-        if let Some(rest) = rest {
+        if rest == Some("") {
+            // trailing slash: the node to remove must be a directory
+            if !matches!(name, "" | "." | "..") {
+                let node = self.child(name).ok_or(VfsError::NotFound)?;
+                if !node.get_attr()?.is_dir() {
+                    return Err(VfsError::NotADirectory);
+                }
+            }
+            self.remove(name)
+        } else if let Some(rest) = rest {
             match name {
                 "" | "." => self.remove(rest),
                 ".." => self.parent().ok_or(VfsError::NotFound)?.remove(rest),
                 _ => {
-                    let subdir = self
-                        .children
-                        .read()
-                        .get(name)
-                        .ok_or(VfsError::NotFound)?
-                        .clone();
+                    let subdir = self.child(name).ok_or(VfsError::NotFound)?;
                     subdir.remove(rest)
                 }
             }
@@ -192,13 +428,13 @@ impl VfsNodeOps for DirNode {
 
         let root_arc = self.this.upgrade().unwrap();
 
-        let old_parent_node = VfsNodeOps::lookup(root_arc.clone(), old_parent_path)?;
+        let old_parent_node = VfsNodeOps::lookup(root_arc.clone(), &old_parent_path)?;
         let old_parent = old_parent_node
             .as_any()
             .downcast_ref::<DirNode>()
             .ok_or(VfsError::InvalidInput)?;
 
-        let new_parent_node = VfsNodeOps::lookup(root_arc, new_parent_path)?;
+        let new_parent_node = VfsNodeOps::lookup(root_arc, &new_parent_path)?;
         let new_parent = new_parent_node
             .as_any()
             .downcast_ref::<DirNode>()
@@ -208,13 +444,23 @@ impl VfsNodeOps for DirNode {
             old_parent.rename_node(old_name, new_name)
         } else {
             let mut old_children = old_parent.children.write();
-            let node = old_children.remove(old_name).ok_or(VfsError::NotFound)?;
+            let old_key = match old_parent.find(&old_children, old_name) {
+                Some((key, _)) => key.clone(),
+                None => return Err(VfsError::NotFound),
+            };
+            if old_parent.is_mountpoint(&old_key) {
+                return Err(VfsError::ResourceBusy);
+            }
             let mut new_children = new_parent.children.write();
-            if new_children.contains_key(new_name) {
-                old_children.insert(old_name.into(), node);
+            if new_parent.find(&new_children, new_name).is_some() {
                 return Err(VfsError::AlreadyExists);
             }
+            let node = old_children.remove(&old_key).unwrap();
             new_children.insert(new_name.into(), node);
+            old_parent.entries_changed();
+            new_parent.entries_changed();
+            memory_shrink(old_key.len());
+            memory_grow(new_name.len());
             Ok(())
         }
     }
@@ -224,20 +470,32 @@ impl VfsNodeOps for DirNode {
     }
 }
 
+/// Splits the first component from `path`.
+///
+/// Repeated slashes are collapsed, and trailing slashes leave `Some("")` as
+/// the rest, which means the component must be a directory.
 fn split_path(path: &str) -> (&str, Option<&str>) {
     let trimmed_path = path.trim_start_matches('/');
     trimmed_path.find('/').map_or((trimmed_path, None), |n| {
-        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
+        (
+            &trimmed_path[..n],
+            Some(trimmed_path[n + 1..].trim_start_matches('/')),
+        )
     })
 }
 
-fn split_parent_name(path: &str) -> Result<(&str, &str), VfsError> {
-    let path = path.trim_start_matches('/');
-    if let Some(pos) = path.rfind('/') {
-        let (parent, name) = path.split_at(pos);
-        let name = &name[1..];
-        Ok((if parent.is_empty() { "/" } else { parent }, name))
-    } else {
-        Ok((".", path))
+/// Splits `path` into the canonical path of its parent directory and its last
+/// component.
+///
+/// The path is relative to the root of the filesystem, with or without the
+/// leading slash, so the parent is always absolute: `/` for a root-level name.
+/// Trailing slashes are ignored, and the last component must be a real name,
+/// not `.` or `..`.
+pub(crate) fn split_parent_name(path: &str) -> VfsResult<(String, &str)> {
+    let path = path.trim_end_matches('/');
+    let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+    if matches!(name, "" | "." | "..") {
+        return Err(VfsError::InvalidInput);
     }
+    Ok((axfs_vfs::path::canonicalize(&format!("/{parent}")), name))
 }
@@ -0,0 +1,114 @@
+use alloc::collections::VecDeque;
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType};
+use axfs_vfs::{VfsError, VfsResult};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::Mutex;
+
+/// The maximum number of bytes buffered in a FIFO.
+pub(crate) const FIFO_CAPACITY: usize = 4096;
+
+/// The FIFO (named pipe) node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`]. Bytes written to it are buffered
+/// until they are read, and offsets are ignored.
+///
+/// The node does not block by itself, since it has no access to the
+/// scheduler. The opener records the read and write ends with
+/// [`open_end`](Self::open_end) and [`close_end`](Self::close_end), and an
+/// operation that has to wait for the other end returns
+/// [`VfsError::WouldBlock`] for the caller to retry.
+pub struct FifoNode {
+    buffer: Mutex<VecDeque<u8>>,
+    readers: AtomicUsize,
+    writers: AtomicUsize,
+}
+
+impl FifoNode {
+    pub(super) const fn new() -> Self {
+        Self {
+            buffer: Mutex::new(VecDeque::new()),
+            readers: AtomicUsize::new(0),
+            writers: AtomicUsize::new(0),
+        }
+    }
+
+    /// Records a newly opened end of the FIFO.
+    pub fn open_end(&self, read: bool, write: bool) {
+        if read {
+            self.readers.fetch_add(1, Ordering::AcqRel);
+        }
+        if write {
+            self.writers.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Records that an end opened by [`open_end`](Self::open_end) with the
+    /// same arguments is closed.
+    pub fn close_end(&self, read: bool, write: bool) {
+        if read {
+            self.readers.fetch_sub(1, Ordering::AcqRel);
+        }
+        if write {
+            self.writers.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+impl VfsNodeOps for FifoNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = self.buffer.lock().len();
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::default_file(),
+            VfsNodeType::Fifo,
+            size as _,
+            0,
+        ))
+    }
+
+    /// Reads the buffered bytes. Returns 0 (end of file) if the buffer is
+    /// empty and there is no writer, or [`VfsError::WouldBlock`] if a writer
+    /// may still provide data.
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut buffer = self.buffer.lock();
+        if buffer.is_empty() {
+            return if self.writers.load(Ordering::Acquire) == 0 {
+                Ok(0)
+            } else {
+                Err(VfsError::WouldBlock)
+            };
+        }
+        let n = buf.len().min(buffer.len());
+        for (dst, src) in buf.iter_mut().zip(buffer.drain(..n)) {
+            *dst = src;
+        }
+        Ok(n)
+    }
+
+    /// Buffers as many bytes as fit. Returns [`VfsError::NotConnected`] if
+    /// there is no reader (`EPIPE`), or [`VfsError::WouldBlock`] if the buffer
+    /// is full.
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        if self.readers.load(Ordering::Acquire) == 0 {
+            return Err(VfsError::NotConnected);
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let mut buffer = self.buffer.lock();
+        let n = (FIFO_CAPACITY - buffer.len()).min(buf.len());
+        if n == 0 {
+            return Err(VfsError::WouldBlock);
+        }
+        buffer.extend(&buf[..n]);
+        Ok(n)
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    impl_vfs_non_dir_default! {}
+}
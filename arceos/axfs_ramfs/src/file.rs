@@ -1,56 +1,325 @@
-use alloc::vec::Vec;
-use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsResult};
-use spin::RwLock;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use axfs_vfs::{impl_vfs_non_dir_default, VfsError, VfsNodeAttr, VfsNodeOps, VfsResult};
+use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use spin::{Mutex, RwLock};
+
+use crate::{memory_shrink, memory_try_grow};
+
+/// The size of the pages that file contents are stored in.
+pub(crate) const PAGE_SIZE: usize = 0x1000;
 
 /// The file node in the RAM filesystem.
 ///
 /// It implements [`axfs_vfs::VfsNodeOps`].
+///
+/// The content is stored sparsely: only pages that have been written are
+/// allocated, and reading a hole returns zeros.
 pub struct FileNode {
-    content: RwLock<Vec<u8>>,
+    content: RwLock<Content>,
+}
+
+/// Sparse file content, the populated pages indexed by page number.
+///
+/// Every page has its own lock, so reads and writes only take the outer lock
+/// for reading, and writes to different pages proceed in parallel. The outer
+/// lock is taken for writing only to add or remove pages.
+///
+/// The attributes are cached in `attr` until the size or the allocated pages
+/// change.
+struct Content {
+    size: AtomicUsize,
+    pages: BTreeMap<usize, Box<RwLock<[u8; PAGE_SIZE]>>>,
+    attr: Mutex<Option<VfsNodeAttr>>,
+}
+
+impl Content {
+    const fn new() -> Self {
+        Self {
+            size: AtomicUsize::new(0),
+            pages: BTreeMap::new(),
+            attr: Mutex::new(None),
+        }
+    }
+
+    /// Returns the attributes of the file, computing them if not cached.
+    fn attr(&self) -> VfsNodeAttr {
+        // computed under the cache lock, so an invalidation that follows a
+        // concurrent write is never overwritten by a stale value
+        *self.attr.lock().get_or_insert_with(|| {
+            let blocks = self.allocated() / 512;
+            VfsNodeAttr::new_file(self.size() as _, blocks as _)
+        })
+    }
+
+    /// Drops the cached attributes, called after the size or the allocated
+    /// pages change.
+    fn invalidate_attr(&self) {
+        *self.attr.lock() = None;
+    }
+
+    fn size(&self) -> usize {
+        self.size.load(Ordering::Acquire)
+    }
+
+    /// Copies `buf.len()` bytes from `offset` into `buf`, filling holes with
+    /// zeros. The range must be within the file size.
+    fn read(&self, offset: usize, buf: &mut [u8]) {
+        let mut pos = offset;
+        let mut buf = buf;
+        while !buf.is_empty() {
+            let page_off = pos % PAGE_SIZE;
+            let len = buf.len().min(PAGE_SIZE - page_off);
+            let (dst, rest) = core::mem::take(&mut buf).split_at_mut(len);
+            match self.pages.get(&(pos / PAGE_SIZE)) {
+                Some(page) => dst.copy_from_slice(&page.read()[page_off..page_off + len]),
+                None => dst.fill(0),
+            }
+            pos += len;
+            buf = rest;
+        }
+    }
+
+    /// Checks whether all pages covering `offset..offset + len` are allocated.
+    fn is_populated(&self, offset: usize, len: usize) -> bool {
+        page_range(offset, len).all(|idx| self.pages.contains_key(&idx))
+    }
+
+    /// Allocates the missing pages covering `offset..offset + len`.
+    ///
+    /// Stops at the first page that would exceed the memory limit, and returns
+    /// the number of bytes from `offset` that are covered by allocated pages.
+    fn populate(&mut self, offset: usize, len: usize) -> usize {
+        let mut populated = len;
+        for idx in page_range(offset, len) {
+            if !self.pages.contains_key(&idx) {
+                if !memory_try_grow(PAGE_SIZE) {
+                    populated = (idx * PAGE_SIZE).saturating_sub(offset);
+                    break;
+                }
+                self.pages.insert(idx, Box::new(RwLock::new([0; PAGE_SIZE])));
+            }
+        }
+        self.invalidate_attr();
+        populated
+    }
+
+    /// Writes `buf` at `offset`, extending the file if it ends beyond the
+    /// current size. The pages must have been populated.
+    fn write(&self, offset: usize, buf: &[u8]) {
+        let mut pos = offset;
+        let mut buf = buf;
+        while !buf.is_empty() {
+            let page_off = pos % PAGE_SIZE;
+            let len = buf.len().min(PAGE_SIZE - page_off);
+            let mut page = self.pages[&(pos / PAGE_SIZE)].write();
+            page[page_off..page_off + len].copy_from_slice(&buf[..len]);
+            pos += len;
+            buf = &buf[len..];
+        }
+        // published after the data, so readers never see unwritten bytes
+        if self.size.fetch_max(pos, Ordering::Release) < pos {
+            self.invalidate_attr();
+        }
+    }
+
+    /// Sets the file size. Shrinking frees the pages beyond the new end and
+    /// zeros the tail of the last page, so growing again exposes zeros.
+    fn truncate(&mut self, size: usize) {
+        if size < *self.size.get_mut() {
+            let freed = self.pages.split_off(&size.div_ceil(PAGE_SIZE));
+            memory_shrink(freed.len() * PAGE_SIZE);
+            if let Some(page) = self.pages.get_mut(&(size / PAGE_SIZE)) {
+                page.get_mut()[size % PAGE_SIZE..].fill(0);
+            }
+        }
+        *self.size.get_mut() = size;
+        self.invalidate_attr();
+    }
+
+    /// Returns the number of bytes allocated for the populated pages.
+    fn allocated(&self) -> usize {
+        self.pages.len() * PAGE_SIZE
+    }
+}
+
+/// Returns the indices of the pages covering `offset..offset + len`.
+fn page_range(offset: usize, len: usize) -> Range<usize> {
+    if len == 0 {
+        return 0..0;
+    }
+    offset / PAGE_SIZE..(offset + len).div_ceil(PAGE_SIZE)
+}
+
+/// A piece of the content of a [`FileNode`], passed to
+/// [`FileNode::for_each_chunk`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum FileChunk<'a> {
+    /// The bytes stored in a populated page.
+    Data(&'a [u8]),
+    /// The given number of bytes that are not populated, all zeros.
+    Hole(usize),
 }
 
 impl FileNode {
     pub(super) const fn new() -> Self {
         Self {
-            content: RwLock::new(Vec::new()),
+            content: RwLock::new(Content::new()),
+        }
+    }
+
+    /// Calls `f` with the content of the file in order of offset, without
+    /// copying it.
+    ///
+    /// Each populated page is passed as [`FileChunk::Data`] (cut at the end of
+    /// file), and each run of unpopulated pages between them as
+    /// [`FileChunk::Hole`], which reads as zeros. The first argument of `f` is
+    /// the offset of the chunk. A sparse file therefore costs no more memory
+    /// here than it already occupies.
+    ///
+    /// The read lock on the content, and on the page being passed, is held
+    /// while `f` runs, so `f` must not write to (or truncate) this file,
+    /// otherwise it will deadlock.
+    pub fn for_each_chunk(&self, mut f: impl FnMut(usize, FileChunk<'_>)) {
+        let content = self.content.read();
+        let size = content.size();
+        let mut pos = 0;
+        for (&idx, page) in content.pages.range(..size.div_ceil(PAGE_SIZE)) {
+            let start = idx * PAGE_SIZE;
+            if start > pos {
+                f(pos, FileChunk::Hole(start - pos));
+            }
+            let end = size.min(start + PAGE_SIZE);
+            f(start, FileChunk::Data(&page.read()[..end - start]));
+            pos = end;
+        }
+        if size > pos {
+            f(pos, FileChunk::Hole(size - pos));
+        }
+    }
+
+    /// Writes `buf` to the end of the file, used for files opened with
+    /// `O_APPEND`.
+    ///
+    /// The length is read and the data written under the same write lock, so
+    /// appends from different handles never overwrite each other. Returns the
+    /// offset at which `buf` was written and the number of bytes written,
+    /// which is less than `buf.len()` if the memory limit is reached.
+    pub fn append(&self, buf: &[u8]) -> VfsResult<(u64, usize)> {
+        let mut content = self.content.write();
+        let offset = content.size();
+        let len = content.populate(offset, buf.len());
+        if len == 0 && !buf.is_empty() {
+            return Err(VfsError::StorageFull);
         }
+        content.write(offset, &buf[..len]);
+        Ok((offset as u64, len))
+    }
+
+    /// Appends data produced by `fill` to the end of the file, `chunk_size`
+    /// bytes at a time, without building the whole payload in memory first.
+    ///
+    /// `fill` is called with a buffer of `chunk_size` bytes and returns how
+    /// many of them it filled; returning 0 ends the stream. Each chunk is
+    /// written to the file before `fill` is called again. Returns the total
+    /// number of bytes appended.
+    ///
+    /// The write lock on the content is held throughout, as in
+    /// [`append`](Self::append), so `fill` must not access this file. If the
+    /// memory limit is reached, the bytes that fit are kept and it fails with
+    /// [`VfsError::StorageFull`].
+    pub fn append_from<R: FnMut(&mut [u8]) -> usize>(
+        &self,
+        chunk_size: usize,
+        mut fill: R,
+    ) -> VfsResult<u64> {
+        if chunk_size == 0 {
+            return Err(VfsError::InvalidInput);
+        }
+        let mut content = self.content.write();
+        let mut chunk = vec![0; chunk_size];
+        let mut total = 0;
+        loop {
+            let n = fill(&mut chunk).min(chunk_size);
+            if n == 0 {
+                return Ok(total);
+            }
+            let offset = content.size();
+            let len = content.populate(offset, n);
+            content.write(offset, &chunk[..len]);
+            total += len as u64;
+            if len < n {
+                return Err(VfsError::StorageFull);
+            }
+        }
+    }
+
+    /// Returns the number of bytes of memory allocated for the content, which
+    /// is less than the file size if the file has holes.
+    pub fn allocated_size(&self) -> usize {
+        self.content.read().allocated()
     }
 }
 
 impl VfsNodeOps for FileNode {
     fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
-        Ok(VfsNodeAttr::new_file(self.content.read().len() as _, 0))
+        Ok(self.content.read().attr())
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
-        let mut content = self.content.write();
-        if size < content.len() as u64 {
-            content.truncate(size as _);
-        } else {
-            content.resize(size as _, 0);
-        }
+        self.content.write().truncate(size as _);
         Ok(())
     }
 
+    /// Reads at most `buf.len()` bytes at `offset`. Reading at or beyond the
+    /// end of file returns 0, and a read spanning it returns the bytes before
+    /// it.
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
         let content = self.content.read();
-        let start = content.len().min(offset as usize);
-        let end = content.len().min(offset as usize + buf.len());
-        let src = &content[start..end];
-        buf[..src.len()].copy_from_slice(src);
-        Ok(src.len())
+        let size = content.size();
+        let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+        let start = size.min(offset);
+        let end = size.min(offset.saturating_add(buf.len()));
+        content.read(start, &mut buf[..end - start]);
+        Ok(end - start)
     }
 
+    /// Writes `buf` at `offset`. If the memory limit is reached, only the
+    /// bytes that fit are written; if none fit, fails with
+    /// [`VfsError::StorageFull`]. An empty write never changes the size.
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        // nothing to write, and the size must not change even past the end
+        if buf.is_empty() {
+            return Ok(0);
+        }
         let offset = offset as usize;
-        let mut content = self.content.write();
-        if offset + buf.len() > content.len() {
-            content.resize(offset + buf.len(), 0);
+        let mut len = buf.len();
+        loop {
+            let content = self.content.read();
+            if content.is_populated(offset, len) {
+                content.write(offset, &buf[..len]);
+                return Ok(len);
+            }
+            drop(content);
+            // only adding pages needs exclusive access, not the write itself
+            len = self.content.write().populate(offset, len);
+            if len == 0 {
+                return Err(VfsError::StorageFull);
+            }
         }
-        let dst = &mut content[offset..offset + buf.len()];
-        dst.copy_from_slice(&buf[..dst.len()]);
-        Ok(buf.len())
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
     }
 
     impl_vfs_non_dir_default! {}
 }
+
+impl Drop for FileNode {
+    fn drop(&mut self) {
+        memory_shrink(self.content.get_mut().allocated());
+    }
+}
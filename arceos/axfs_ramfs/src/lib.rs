@@ -6,19 +6,72 @@
 
 extern crate alloc;
 
+mod device;
 mod dir;
+mod fifo;
 mod file;
+mod symlink;
 
 #[cfg(test)]
 mod tests;
 
+pub use self::device::{DeviceNode, DeviceReadFn, DeviceWriteFn};
 pub use self::dir::DirNode;
-pub use self::file::FileNode;
+pub use self::fifo::FifoNode;
+pub use self::file::{FileChunk, FileNode};
+pub use self::symlink::SymlinkNode;
 
 use alloc::sync::Arc;
 use axfs_vfs::{VfsNodeRef, VfsOps, VfsResult};
+use core::sync::atomic::{AtomicUsize, Ordering};
 use spin::once::Once;
 
+static MEMORY_USAGE: AtomicUsize = AtomicUsize::new(0);
+static MEMORY_LIMIT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Returns the number of bytes currently used by all RAM filesystems.
+///
+/// It counts the pages allocated for file contents (holes in sparse files
+/// take no memory) and the names of all directory entries.
+pub fn ramfs_memory_usage() -> usize {
+    MEMORY_USAGE.load(Ordering::Relaxed)
+}
+
+/// Returns the maximum number of bytes that all RAM filesystems may use, see
+/// [`set_ramfs_memory_limit`].
+pub fn ramfs_memory_limit() -> usize {
+    MEMORY_LIMIT.load(Ordering::Relaxed)
+}
+
+/// Limits the memory used by all RAM filesystems to `limit` bytes.
+///
+/// It is usually set once when the filesystem is initialized. Writes that need
+/// more memory than the limit allows are cut short, or fail with
+/// [`VfsError::StorageFull`](axfs_vfs::VfsError::StorageFull) if nothing can be
+/// written. Memory already in use is not reclaimed when the limit is lowered.
+/// There is no limit by default.
+pub fn set_ramfs_memory_limit(limit: usize) {
+    MEMORY_LIMIT.store(limit, Ordering::Relaxed);
+}
+
+fn memory_grow(size: usize) {
+    MEMORY_USAGE.fetch_add(size, Ordering::Relaxed);
+}
+
+/// Like [`memory_grow`], but fails if the usage would exceed the limit.
+fn memory_try_grow(size: usize) -> bool {
+    let limit = ramfs_memory_limit();
+    MEMORY_USAGE
+        .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+            usage.checked_add(size).filter(|&new| new <= limit)
+        })
+        .is_ok()
+}
+
+fn memory_shrink(size: usize) {
+    MEMORY_USAGE.fetch_sub(size, Ordering::Relaxed);
+}
+
 /// A RAM filesystem that implements [`axfs_vfs::VfsOps`].
 pub struct RamFileSystem {
     parent: Once<VfsNodeRef>,
@@ -30,7 +83,18 @@ impl RamFileSystem {
     pub fn new() -> Self {
         Self {
             parent: Once::new(),
-            root: DirNode::new(None),
+            root: DirNode::new(None, false),
+        }
+    }
+
+    /// Create a new instance whose lookups ignore ASCII case, like FAT.
+    ///
+    /// Names are still stored with their original case, but creating a name
+    /// that only differs in case from an existing one fails.
+    pub fn new_case_insensitive() -> Self {
+        Self {
+            parent: Once::new(),
+            root: DirNode::new(None, true),
         }
     }
 
@@ -0,0 +1,52 @@
+use alloc::string::String;
+use axfs_vfs::{impl_vfs_non_dir_default, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType};
+use axfs_vfs::VfsResult;
+
+use crate::{memory_grow, memory_shrink};
+
+/// The symbolic link node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`]. It only stores the target path,
+/// which is not checked or followed by the filesystem itself: resolving it is
+/// up to the caller, like the kernel's path walk.
+pub struct SymlinkNode {
+    target: String,
+}
+
+impl SymlinkNode {
+    pub(super) fn new(target: &str) -> Self {
+        memory_grow(target.len());
+        Self {
+            target: target.into(),
+        }
+    }
+
+    /// Returns the path that the link points to.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        // like Linux, the size of a symlink is the length of its target
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o777),
+            VfsNodeType::SymLink,
+            self.target.len() as _,
+            0,
+        ))
+    }
+
+    fn as_any(&self) -> &dyn core::any::Any {
+        self
+    }
+
+    impl_vfs_non_dir_default! {}
+}
+
+impl Drop for SymlinkNode {
+    fn drop(&mut self) {
+        memory_shrink(self.target.len());
+    }
+}
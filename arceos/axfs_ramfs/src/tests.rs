@@ -1,9 +1,16 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
 
-use axfs_vfs::{VfsError, VfsNodeType, VfsResult};
+use axfs_vfs::{VfsDirEntry, VfsError, VfsNodeOps, VfsNodeType, VfsResult};
 
+use crate::file::PAGE_SIZE;
 use crate::*;
 
+/// Serializes the tests, since [`ramfs_memory_usage`] is shared by all of them.
+fn lock_tests() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
 fn test_ramfs_ops(devfs: &RamFileSystem) -> VfsResult {
     const N: usize = 32;
     const N_HALF: usize = N / 2;
@@ -90,6 +97,7 @@ fn test_get_parent(devfs: &RamFileSystem) -> VfsResult {
 
 #[test]
 fn test_ramfs() {
+    let _guard = lock_tests();
     // .
     // ├── foo
     // │   ├── bar
@@ -134,3 +142,765 @@ fn test_ramfs() {
     assert_eq!(root.remove("./foo"), Ok(()));
     assert!(ramfs.root_dir_node().get_entries().is_empty());
 }
+
+/// Collects the whole content of `file`, with holes filled with zeros.
+fn file_bytes(file: &FileNode) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    file.for_each_chunk(|offset, chunk| {
+        assert_eq!(offset, bytes.len());
+        match chunk {
+            FileChunk::Data(data) => bytes.extend_from_slice(data),
+            FileChunk::Hole(len) => bytes.resize(bytes.len() + len, 0),
+        }
+    });
+    bytes
+}
+
+#[test]
+fn test_file_for_each_chunk() {
+    let _guard = lock_tests();
+    let file = FileNode::new();
+    let mut calls = 0;
+    file.for_each_chunk(|_, _| calls += 1);
+    assert_eq!(calls, 0);
+
+    file.write_at(0, b"hello, arceos!").unwrap();
+    let mut checksum = 0;
+    file.for_each_chunk(|_, chunk| {
+        if let FileChunk::Data(data) = chunk {
+            checksum += data.iter().map(|&b| b as u32).sum::<u32>();
+        }
+    });
+    assert_eq!(checksum, b"hello, arceos!".iter().map(|&b| b as u32).sum());
+
+    // The lock is released after the closure returns.
+    file.write_at(14, b"\n").unwrap();
+    assert_eq!(file_bytes(&file), b"hello, arceos!\n");
+
+    // Only the populated page of a sparse file is passed, the rest are holes.
+    let sparse = FileNode::new();
+    sparse.write_at(PAGE_SIZE as u64 * 2, b"data").unwrap();
+    sparse.truncate(1 << 20).unwrap();
+    let mut chunks = Vec::new();
+    sparse.for_each_chunk(|offset, chunk| {
+        chunks.push(match chunk {
+            FileChunk::Data(data) => (offset, true, data.len()),
+            FileChunk::Hole(len) => (offset, false, len),
+        });
+    });
+    let page = PAGE_SIZE;
+    assert_eq!(
+        chunks,
+        [(0, false, 2 * page), (2 * page, true, page), (3 * page, false, (1 << 20) - 3 * page)]
+    );
+    assert_eq!(sparse.allocated_size(), PAGE_SIZE);
+}
+
+#[test]
+fn test_file_append() {
+    let _guard = lock_tests();
+    const N: usize = 100;
+    let file = Arc::new(FileNode::new());
+    file.write_at(0, b"head").unwrap();
+
+    let handles: Vec<_> = [b'a', b'b']
+        .into_iter()
+        .map(|c| {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                for _ in 0..N {
+                    file.append(&[c; 4]).unwrap();
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    let bytes = file_bytes(&file);
+    assert_eq!(bytes.len(), 4 + 2 * N * 4);
+    assert_eq!(&bytes[..4], b"head");
+    // Every appended chunk is intact, none of them was overwritten.
+    let chunks = bytes[4..].chunks(4);
+    assert!(chunks.clone().all(|c| c == [b'a'; 4] || c == [b'b'; 4]));
+    assert_eq!(chunks.filter(|c| c[0] == b'a').count(), N);
+}
+
+#[test]
+fn test_file_append_from() {
+    let _guard = lock_tests();
+    const LEN: usize = 1 << 20;
+    let file = FileNode::new();
+    file.write_at(0, b"head").unwrap();
+
+    // Stream 1 MiB in 4 KiB chunks; the last call fills nothing.
+    let mut produced = 0;
+    let mut calls = 0;
+    let written = file
+        .append_from(PAGE_SIZE, |chunk| {
+            calls += 1;
+            let n = chunk.len().min(LEN - produced);
+            for (i, b) in chunk[..n].iter_mut().enumerate() {
+                *b = ((produced + i) % 251) as u8;
+            }
+            produced += n;
+            n
+        })
+        .unwrap();
+    assert_eq!(written, LEN as u64);
+    assert_eq!(calls, LEN / PAGE_SIZE + 1);
+    assert_eq!(file.get_attr().unwrap().size(), 4 + LEN as u64);
+    let bytes = file_bytes(&file);
+    assert_eq!(&bytes[..4], b"head");
+    assert!(bytes[4..].iter().enumerate().all(|(i, &b)| b == (i % 251) as u8));
+
+    assert_eq!(file.append_from(0, |_| 1), Err(VfsError::InvalidInput));
+    assert_eq!(file.append_from(PAGE_SIZE, |_| 0), Ok(0));
+}
+
+#[test]
+fn test_memory_usage() {
+    let _guard = lock_tests();
+    let base = ramfs_memory_usage();
+
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("dir", VfsNodeType::Dir).unwrap();
+    assert_eq!(ramfs_memory_usage(), base + 5);
+
+    // File contents are allocated in whole pages.
+    let f1 = root.clone().lookup("f1").unwrap();
+    f1.write_at(0, &[1; 100]).unwrap();
+    f1.write_at(50, &[2; 100]).unwrap();
+    assert_eq!(ramfs_memory_usage(), base + 5 + PAGE_SIZE);
+    f1.write_at(PAGE_SIZE as _, &[3; 100]).unwrap();
+    assert_eq!(ramfs_memory_usage(), base + 5 + 2 * PAGE_SIZE);
+    f1.truncate(10).unwrap();
+    assert_eq!(ramfs_memory_usage(), base + 5 + PAGE_SIZE);
+
+    // Growing a file with `truncate` only leaves a hole.
+    root.create("dir/f2", VfsNodeType::File).unwrap();
+    let f2 = root.clone().lookup("dir/f2").unwrap();
+    f2.truncate(4096).unwrap();
+    assert_eq!(ramfs_memory_usage(), base + 5 + PAGE_SIZE + 2);
+    f2.write_at(0, &[4; 10]).unwrap();
+    assert_eq!(ramfs_memory_usage(), base + 5 + 2 * PAGE_SIZE + 2);
+    drop(f2);
+
+    // The content is reclaimed once the last reference to the node is gone.
+    root.remove("f1").unwrap();
+    assert_eq!(ramfs_memory_usage(), base + 3 + 2 * PAGE_SIZE + 2);
+    drop(f1);
+    assert_eq!(ramfs_memory_usage(), base + 3 + PAGE_SIZE + 2);
+
+    drop(root);
+    drop(ramfs);
+    assert_eq!(ramfs_memory_usage(), base);
+}
+
+#[test]
+fn test_slashes() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("a", VfsNodeType::Dir).unwrap();
+    root.create("a//f", VfsNodeType::File).unwrap();
+    root.create("a///d/", VfsNodeType::Dir).unwrap();
+
+    // Repeated slashes are collapsed.
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("a//f").unwrap(),
+        &root.clone().lookup("a/f").unwrap(),
+    ));
+    assert!(root.clone().lookup("a//d//").unwrap().get_attr().unwrap().is_dir());
+
+    // A trailing slash requires a directory.
+    assert_eq!(
+        root.clone().lookup("a/f/").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.create("a/g/", VfsNodeType::File).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(root.remove("a/f/").err(), Some(VfsError::NotADirectory));
+    assert_eq!(root.remove("a/d/"), Ok(()));
+    assert_eq!(root.remove("a//f"), Ok(()));
+    assert_eq!(root.remove("a/"), Ok(()));
+    assert!(ramfs.root_dir_node().get_entries().is_empty());
+}
+
+#[test]
+fn test_create_all() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+
+    root.create_all("a/b/c/file", VfsNodeType::File).unwrap();
+    let node = root.clone().lookup("a/b/c/file").unwrap();
+    assert_eq!(node.get_attr().unwrap().file_type(), VfsNodeType::File);
+    assert!(root.clone().lookup("a/b").unwrap().get_attr().unwrap().is_dir());
+
+    // Existing components are reused.
+    node.write_at(0, b"data").unwrap();
+    root.create_all("a//b/c/file", VfsNodeType::File).unwrap();
+    root.create_all("a/b/d/", VfsNodeType::Dir).unwrap();
+    assert_eq!(root.clone().lookup("a/b/c/file").unwrap().get_attr().unwrap().size(), 4);
+    assert!(root.clone().lookup("a/b/d").unwrap().get_attr().unwrap().is_dir());
+
+    // A file can't be created with a trailing slash, nor used as a directory.
+    assert_eq!(
+        root.create_all("a/b/c/file", VfsNodeType::Dir).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        root.create_all("a/b/c/file/x", VfsNodeType::File).err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(
+        root.create_all("a/new/", VfsNodeType::File).err(),
+        Some(VfsError::NotADirectory)
+    );
+
+    // Missing directories after `..` are created as well.
+    let b = root.clone().lookup("a/b").unwrap();
+    let b = b.as_any().downcast_ref::<DirNode>().unwrap();
+    b.create_all("../x/y/z", VfsNodeType::File).unwrap();
+    let node = root.clone().lookup("a/x/y/z").unwrap();
+    assert_eq!(node.get_attr().unwrap().file_type(), VfsNodeType::File);
+    b.create_all("c/../../x/w/", VfsNodeType::Dir).unwrap();
+    assert!(root.clone().lookup("a/x/w").unwrap().get_attr().unwrap().is_dir());
+}
+
+#[test]
+fn test_dir_len() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    assert!(root.is_empty());
+    assert_eq!(root.get_attr().unwrap().size(), 0);
+
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("f2", VfsNodeType::File).unwrap();
+    root.create("d", VfsNodeType::Dir).unwrap();
+    root.create("d/f3", VfsNodeType::File).unwrap();
+    assert_eq!(root.len(), 3);
+    assert!(!root.is_empty());
+    assert_eq!(root.get_attr().unwrap().size(), 3);
+    assert_eq!(root.clone().lookup("d").unwrap().get_attr().unwrap().size(), 1);
+
+    root.remove("f1").unwrap();
+    root.remove("d/f3").unwrap();
+    root.remove("d").unwrap();
+    assert_eq!(root.len(), 1);
+    root.remove("f2").unwrap();
+    assert!(root.is_empty());
+    assert_eq!(root.get_attr().unwrap().size(), 0);
+}
+
+#[test]
+fn test_dir_entries() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    root.create("f1", VfsNodeType::File).unwrap();
+    root.create("d", VfsNodeType::Dir).unwrap();
+    root.create("d/f2", VfsNodeType::File).unwrap();
+
+    let entries = root.entries();
+    let names: Vec<_> = entries.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, ["d", "f1"]);
+    for (name, node) in entries {
+        assert!(Arc::ptr_eq(&node, &root.clone().lookup(&name).unwrap()));
+        let ty = node.get_attr().unwrap().file_type();
+        match name.as_str() {
+            "d" => assert_eq!(ty, VfsNodeType::Dir),
+            _ => assert_eq!(ty, VfsNodeType::File),
+        }
+    }
+}
+
+#[test]
+fn test_case_insensitive() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new_case_insensitive();
+    let root = ramfs.root_dir_node();
+    root.create("FILE.TXT", VfsNodeType::File).unwrap();
+    root.create("Dir", VfsNodeType::Dir).unwrap();
+    root.create("dir/Sub.txt", VfsNodeType::File).unwrap();
+
+    // lookups ignore case
+    let file = root.clone().lookup("FILE.TXT").unwrap();
+    assert!(Arc::ptr_eq(&file, &root.clone().lookup("file.txt").unwrap()));
+    assert!(root.exist("File.Txt"));
+    assert!(Arc::ptr_eq(
+        &root.clone().lookup("DIR/sub.TXT").unwrap(),
+        &root.clone().lookup("dir/Sub.txt").unwrap(),
+    ));
+
+    // the original case is preserved
+    assert_eq!(root.get_entries(), ["Dir", "FILE.TXT"]);
+
+    // names only differing in case collide
+    assert_eq!(
+        root.create("file.txt", VfsNodeType::File).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    assert_eq!(
+        root.create("DIR/sub.txt", VfsNodeType::File).err(),
+        Some(VfsError::AlreadyExists)
+    );
+
+    // changing only the case of a name is allowed
+    root.rename("file.txt", "File.txt").unwrap();
+    assert_eq!(root.get_entries(), ["Dir", "File.txt"]);
+    root.remove("FILE.txt").unwrap();
+    assert_eq!(root.get_entries(), ["Dir"]);
+
+    // the default is still case-sensitive
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    root.create("FILE.TXT", VfsNodeType::File).unwrap();
+    root.create("file.txt", VfsNodeType::File).unwrap();
+    assert!(!root.exist("File.Txt"));
+    assert_eq!(root.len(), 2);
+}
+
+#[test]
+fn test_sparse_file() {
+    let _guard = lock_tests();
+    const MIB: usize = 1024 * 1024;
+    let base = ramfs_memory_usage();
+    let file = FileNode::new();
+
+    file.write_at(MIB as _, &[0xaa]).unwrap();
+    assert_eq!(file.get_attr().unwrap().size(), MIB as u64 + 1);
+    assert_eq!(file.allocated_size(), PAGE_SIZE);
+    assert_eq!(ramfs_memory_usage(), base + PAGE_SIZE);
+
+    // Holes read as zeros, also across the boundary of a populated page.
+    let mut buf = [1; 16];
+    assert_eq!(file.read_at(12345, &mut buf).unwrap(), 16);
+    assert_eq!(buf, [0; 16]);
+    assert_eq!(file.read_at(MIB as u64 - 8, &mut buf).unwrap(), 9);
+    assert_eq!(buf[..9], [0, 0, 0, 0, 0, 0, 0, 0, 0xaa]);
+
+    // Shrinking frees the pages, and growing again exposes zeros.
+    file.write_at(4, b"hello, arceos!").unwrap();
+    file.truncate(6).unwrap();
+    assert_eq!(file.allocated_size(), PAGE_SIZE);
+    file.truncate(PAGE_SIZE as u64 + 1).unwrap();
+    assert_eq!(file.read_at(0, &mut buf).unwrap(), 16);
+    assert_eq!(&buf, b"\0\0\0\0he\0\0\0\0\0\0\0\0\0\0");
+
+    drop(file);
+    assert_eq!(ramfs_memory_usage(), base);
+}
+
+#[test]
+fn test_file_concurrent_pages() {
+    let _guard = lock_tests();
+    const N: usize = 4;
+    let file = Arc::new(FileNode::new());
+
+    // Every thread keeps rewriting its own page, in parallel with the others.
+    let handles: Vec<_> = (0..N)
+        .map(|i| {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                for round in 0..100u8 {
+                    let data = [i as u8 * 16 + round % 16; PAGE_SIZE];
+                    file.write_at((i * PAGE_SIZE) as _, &data).unwrap();
+                }
+            })
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    assert_eq!(file.get_attr().unwrap().size(), (N * PAGE_SIZE) as u64);
+    let mut buf = [0; PAGE_SIZE];
+    for i in 0..N {
+        file.read_at((i * PAGE_SIZE) as _, &mut buf).unwrap();
+        assert!(buf.iter().all(|&b| b == i as u8 * 16 + 99 % 16));
+    }
+}
+
+#[test]
+fn test_file_page_boundary() {
+    let _guard = lock_tests();
+    let file = FileNode::new();
+    let data: Vec<u8> = (0..=255).cycle().take(3 * PAGE_SIZE).collect();
+
+    // Writes and reads spanning several pages, not aligned to any of them.
+    file.write_at(100, &data).unwrap();
+    assert_eq!(file.get_attr().unwrap().size(), 100 + data.len() as u64);
+    let mut buf = vec![0; data.len()];
+    assert_eq!(file.read_at(100, &mut buf).unwrap(), data.len());
+    assert_eq!(buf, data);
+
+    let mut buf = [0; 8];
+    let boundary = PAGE_SIZE as u64 - 4;
+    assert_eq!(file.read_at(boundary, &mut buf).unwrap(), 8);
+    assert_eq!(buf[..], data[PAGE_SIZE - 104..PAGE_SIZE - 96]);
+    file.write_at(boundary, b"abcdefgh").unwrap();
+    assert_eq!(file.read_at(boundary, &mut buf).unwrap(), 8);
+    assert_eq!(&buf, b"abcdefgh");
+    assert_eq!(file_bytes(&file)[..100], [0; 100]);
+}
+
+#[test]
+fn test_file_empty_write() {
+    let _guard = lock_tests();
+    let file = FileNode::new();
+    file.write_at(0, b"data").unwrap();
+
+    // An empty write past the end neither extends the file nor allocates.
+    assert_eq!(file.write_at(1 << 20, b"").unwrap(), 0);
+    assert_eq!(file.get_attr().unwrap().size(), 4);
+    assert_eq!(file.allocated_size(), PAGE_SIZE);
+    assert_eq!(file.write_at(2, b"").unwrap(), 0);
+    assert_eq!(file_bytes(&file), b"data");
+}
+
+#[test]
+fn test_fifo() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("fifo", VfsNodeType::Fifo).unwrap();
+    let fifo = root.clone().lookup("fifo").unwrap();
+    assert_eq!(fifo.get_attr().unwrap().file_type(), VfsNodeType::Fifo);
+    let node = fifo.as_any().downcast_ref::<FifoNode>().unwrap();
+
+    // Without a writer, a read on the empty FIFO is at end of file, and
+    // without a reader a write fails.
+    let mut buf = [0; 8];
+    assert_eq!(fifo.read_at(0, &mut buf).unwrap(), 0);
+    assert_eq!(fifo.write_at(0, b"hello"), Err(VfsError::NotConnected));
+
+    // Bytes come out in the order they went in, and offsets are ignored.
+    node.open_end(true, false);
+    node.open_end(false, true);
+    assert_eq!(fifo.write_at(0, b"hello").unwrap(), 5);
+    assert_eq!(fifo.write_at(100, b", arceos").unwrap(), 8);
+    assert_eq!(fifo.read_at(0, &mut buf).unwrap(), 8);
+    assert_eq!(&buf, b"hello, a");
+    assert_eq!(fifo.read_at(0, &mut buf).unwrap(), 5);
+    assert_eq!(&buf[..5], b"rceos");
+
+    // With a writer, a read on the empty FIFO has to wait.
+    assert_eq!(fifo.read_at(0, &mut buf), Err(VfsError::WouldBlock));
+
+    // A full buffer takes what fits, then the writer has to wait.
+    let data = vec![7; crate::fifo::FIFO_CAPACITY + 10];
+    assert_eq!(fifo.write_at(0, &data).unwrap(), crate::fifo::FIFO_CAPACITY);
+    assert_eq!(fifo.write_at(0, &data), Err(VfsError::WouldBlock));
+
+    // Buffered bytes can still be read after the writer is gone.
+    node.close_end(false, true);
+    let mut big = vec![0; crate::fifo::FIFO_CAPACITY + 10];
+    assert_eq!(fifo.read_at(0, &mut big).unwrap(), crate::fifo::FIFO_CAPACITY);
+    assert_eq!(fifo.read_at(0, &mut buf).unwrap(), 0);
+
+    // Once the reader is gone too, writes fail again.
+    node.close_end(true, false);
+    assert_eq!(fifo.write_at(0, b"x"), Err(VfsError::NotConnected));
+}
+
+#[test]
+fn test_char_device() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+
+    // A char device created by type is a null device.
+    root.create("null", VfsNodeType::CharDevice).unwrap();
+    let null = root.clone().lookup("null").unwrap();
+    assert_eq!(null.get_attr().unwrap().file_type(), VfsNodeType::CharDevice);
+    assert_eq!(null.write_at(0, b"discarded").unwrap(), 9);
+    let mut buf = [1; 4];
+    assert_eq!(null.read_at(0, &mut buf).unwrap(), 0);
+    assert_eq!(buf, [1; 4]);
+    let dev = null.as_any().downcast_ref::<DeviceNode>().unwrap();
+    assert_eq!((dev.major(), dev.minor()), (1, 3));
+
+    // Other devices are added with their own callbacks.
+    fn zero_read(_offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        buf.fill(0);
+        Ok(buf.len())
+    }
+    fn zero_write(_offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        Ok(buf.len())
+    }
+    let zero = DeviceNode::new(1, 5, zero_read, zero_write);
+    root.add_device("zero", zero).unwrap();
+    assert_eq!(
+        root.add_device("zero", DeviceNode::null()).err(),
+        Some(VfsError::AlreadyExists)
+    );
+    let zero = root.clone().lookup("zero").unwrap();
+    assert_eq!(zero.read_at(0, &mut buf).unwrap(), 4);
+    assert_eq!(buf, [0; 4]);
+    assert_eq!(root.get_entries(), ["null", "zero"]);
+}
+
+#[test]
+fn test_attr_cache() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    root.create("f", VfsNodeType::File).unwrap();
+    root.create("d", VfsNodeType::Dir).unwrap();
+    let file = root.clone().lookup("f").unwrap();
+    file.write_at(0, b"hello").unwrap();
+
+    // Listing twice gives the same entries and attributes.
+    let list = || {
+        let mut dirents = [VfsDirEntry::default(); 8];
+        let n = root.read_dir(0, &mut dirents).unwrap();
+        dirents[..n]
+            .iter()
+            .map(|ent| {
+                let name = core::str::from_utf8(ent.name_as_bytes()).unwrap().to_string();
+                let attr = root.clone().lookup(&name).unwrap().get_attr().unwrap();
+                (name, ent.entry_type(), attr.size(), attr.blocks())
+            })
+            .collect::<Vec<_>>()
+    };
+    let first = list();
+    assert_eq!(first.len(), 4);
+    assert_eq!(first, list());
+
+    // Writes and truncates invalidate the cached file size.
+    assert_eq!(file.get_attr().unwrap().size(), 5);
+    file.write_at(PAGE_SIZE as u64, b"world").unwrap();
+    assert_eq!(file.get_attr().unwrap().size(), PAGE_SIZE as u64 + 5);
+    assert_eq!(file.get_attr().unwrap().blocks(), 2 * PAGE_SIZE as u64 / 512);
+    file.truncate(3).unwrap();
+    assert_eq!(file.get_attr().unwrap().size(), 3);
+    assert_eq!(file.get_attr().unwrap().blocks(), PAGE_SIZE as u64 / 512);
+
+    // Creates and removes invalidate the cached directory size.
+    assert_eq!(root.get_attr().unwrap().size(), 2);
+    root.create("g", VfsNodeType::File).unwrap();
+    assert_eq!(root.get_attr().unwrap().size(), 3);
+    root.remove("g").unwrap();
+    root.remove("d").unwrap();
+    assert_eq!(root.get_attr().unwrap().size(), 1);
+    assert_ne!(first, list());
+}
+
+#[test]
+fn test_mount() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let other = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    let other_root = other.root_dir_node();
+    root.create("mnt", VfsNodeType::Dir).unwrap();
+    root.create("f", VfsNodeType::File).unwrap();
+    let host_mnt = root.clone().lookup("mnt").unwrap();
+
+    assert_eq!(root.mount("none", other_root.clone()).err(), Some(VfsError::NotFound));
+    assert_eq!(root.mount("f", other_root.clone()).err(), Some(VfsError::NotADirectory));
+    root.mount("mnt", other_root.clone()).unwrap();
+    assert_eq!(root.mount("mnt", other_root.clone()).err(), Some(VfsError::ResourceBusy));
+
+    // Files created through the mountpoint land in the mounted tree.
+    root.create("/mnt/file", VfsNodeType::File).unwrap();
+    root.create_all("/mnt/d/g", VfsNodeType::File).unwrap();
+    let file = root.clone().lookup("/mnt/file").unwrap();
+    assert!(Arc::ptr_eq(&file, &other_root.clone().lookup("file").unwrap()));
+    assert!(other_root.exist("d"));
+    let host_mnt = host_mnt.as_any().downcast_ref::<DirNode>().unwrap();
+    assert!(host_mnt.is_empty());
+
+    // `..` in the mounted tree goes back to the mountpoint's parent.
+    let back = root.clone().lookup("/mnt/d/../..").unwrap();
+    assert!(Arc::ptr_eq(&back, &(root.clone() as VfsNodeRef)));
+    assert!(root.clone().lookup("/mnt/../f").is_ok());
+
+    // A mountpoint can't be removed or renamed while mounted.
+    assert_eq!(root.remove("mnt").err(), Some(VfsError::ResourceBusy));
+    assert_eq!(root.rename("mnt", "mnt2").err(), Some(VfsError::ResourceBusy));
+
+    root.remove("/mnt/file").unwrap();
+    assert!(!other_root.exist("file"));
+
+    // After unmounting, the hidden directory is visible again.
+    let unmounted = root.unmount("mnt").unwrap();
+    assert!(Arc::ptr_eq(&unmounted, &(other_root.clone() as VfsNodeRef)));
+    assert_eq!(root.clone().lookup("/mnt/d").err(), Some(VfsError::NotFound));
+    assert_eq!(root.unmount("mnt").err(), Some(VfsError::InvalidInput));
+    root.remove("mnt").unwrap();
+}
+
+#[test]
+fn test_read_at_eof() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    root.create("f", VfsNodeType::File).unwrap();
+    let file = root.lookup("f").unwrap();
+    let mut buf = [0xffu8; 16];
+    assert_eq!(file.read_at(0, &mut buf).unwrap(), 0);
+
+    file.write_at(0, b"0123456789").unwrap();
+    let len = 10;
+    // At or beyond EOF nothing is read, and the buffer is left untouched.
+    assert_eq!(file.read_at(len, &mut buf).unwrap(), 0);
+    assert_eq!(file.read_at(len + 1, &mut buf).unwrap(), 0);
+    assert_eq!(file.read_at(u64::MAX, &mut buf).unwrap(), 0);
+    assert_eq!(buf, [0xff; 16]);
+
+    // A read spanning EOF is short.
+    assert_eq!(file.read_at(len - 1, &mut buf[..11]).unwrap(), 1);
+    assert_eq!(buf[0], b'9');
+    assert_eq!(file.read_at(4, &mut buf).unwrap(), 6);
+    assert_eq!(&buf[..6], b"456789");
+
+    // So does a read after the file is truncated.
+    file.truncate(5).unwrap();
+    assert_eq!(file.read_at(4, &mut buf).unwrap(), 1);
+    assert_eq!(file.read_at(5, &mut buf).unwrap(), 0);
+}
+
+#[test]
+fn test_memory_limit() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("f", VfsNodeType::File).unwrap();
+    let f = root.clone().lookup("f").unwrap();
+    set_ramfs_memory_limit(ramfs_memory_usage() + 2 * PAGE_SIZE);
+
+    // The write is cut short at the limit.
+    let data = [7; 3 * PAGE_SIZE];
+    assert_eq!(f.write_at(100, &data), Ok(2 * PAGE_SIZE - 100));
+    assert_eq!(f.get_attr().unwrap().size(), 2 * PAGE_SIZE as u64);
+
+    // Nothing more can be written beyond the allocated pages, but writes and
+    // reads within them still work.
+    assert_eq!(f.write_at(2 * PAGE_SIZE as _, &[1]), Err(VfsError::StorageFull));
+    let file = f.as_any().downcast_ref::<FileNode>().unwrap();
+    assert_eq!(file.append(&[1]), Err(VfsError::StorageFull));
+    assert_eq!(f.write_at(0, &[8; 100]), Ok(100));
+    let mut buf = [0; 200];
+    assert_eq!(f.read_at(50, &mut buf), Ok(200));
+    assert_eq!(buf[..50], [8; 50]);
+    assert_eq!(buf[50..], [7; 150]);
+
+    // Freeing pages makes room again.
+    f.truncate(PAGE_SIZE as _).unwrap();
+    assert_eq!(file.append(&[9; 10]), Ok((PAGE_SIZE as u64, 10)));
+
+    set_ramfs_memory_limit(usize::MAX);
+}
+
+#[test]
+fn test_dir_generation() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    root.create("d", VfsNodeType::Dir).unwrap();
+    let d = root.clone().lookup("d").unwrap();
+    let dir = d.as_any().downcast_ref::<DirNode>().unwrap();
+
+    let mut last = root.generation();
+    let mut advanced = |gen: u64| {
+        let ok = gen > last;
+        last = gen;
+        ok
+    };
+    root.create("f", VfsNodeType::File).unwrap();
+    assert!(advanced(root.generation()));
+    root.rename("f", "g").unwrap();
+    assert!(advanced(root.generation()));
+    root.remove("g").unwrap();
+    assert!(advanced(root.generation()));
+
+    // Lookups, reads and failed operations leave it unchanged.
+    root.clone().lookup("d").unwrap();
+    root.read_dir(0, &mut [VfsDirEntry::default(); 4]).unwrap();
+    assert!(root.create("d", VfsNodeType::Dir).is_err());
+    assert_eq!(root.generation(), last);
+
+    // Moving an entry to another directory changes both.
+    root.create("h", VfsNodeType::File).unwrap();
+    let (root_gen, dir_gen) = (root.generation(), dir.generation());
+    root.rename("h", "d/h").unwrap();
+    assert!(root.generation() > root_gen);
+    assert!(dir.generation() > dir_gen);
+}
+
+#[test]
+fn test_split_parent_name() {
+    use crate::dir::split_parent_name;
+
+    let _guard = lock_tests();
+    let split = |path: &str| split_parent_name(path).map(|(parent, name)| (parent, name.to_string()));
+    let ok = |parent: &str, name: &str| Ok((parent.to_string(), name.to_string()));
+    assert_eq!(split("/file"), ok("/", "file"));
+    assert_eq!(split("a"), ok("/", "a"));
+    assert_eq!(split("a/b"), ok("/a", "b"));
+    assert_eq!(split("/a/b/c"), ok("/a/b", "c"));
+    assert_eq!(split("a//b/./c/"), ok("/a/b", "c"));
+    assert_eq!(split("/"), Err(VfsError::InvalidInput));
+    assert_eq!(split("a/.."), Err(VfsError::InvalidInput));
+
+    // Renames with root-level and nested names on both sides.
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("a", VfsNodeType::Dir).unwrap();
+    root.create("a/b", VfsNodeType::Dir).unwrap();
+    root.create("file", VfsNodeType::File).unwrap();
+    root.rename("/file", "/a/b/c").unwrap();
+    assert!(root.clone().lookup("a/b/c").is_ok());
+    root.rename("a/b/c", "file").unwrap();
+    assert!(root.clone().lookup("file").is_ok());
+    assert_eq!(root.clone().lookup("a/b/c").err(), Some(VfsError::NotFound));
+}
+
+#[test]
+fn test_create_through_file() {
+    let _guard = lock_tests();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir();
+    root.create("a", VfsNodeType::File).unwrap();
+    assert_eq!(root.create("a/b", VfsNodeType::File), Err(VfsError::NotADirectory));
+    assert_eq!(root.create("a/b/c", VfsNodeType::Dir), Err(VfsError::NotADirectory));
+    assert_eq!(root.create("./a/b", VfsNodeType::File), Err(VfsError::NotADirectory));
+    assert!(root.clone().lookup("a").unwrap().get_attr().unwrap().is_file());
+}
+
+#[test]
+fn test_symlink() {
+    let _guard = lock_tests();
+    let base = ramfs_memory_usage();
+    let ramfs = RamFileSystem::new();
+    let root = ramfs.root_dir_node();
+    root.create_symlink("link", "/some/where").unwrap();
+    assert_eq!(root.create_symlink("link", "x"), Err(VfsError::AlreadyExists));
+    assert_eq!(root.create_symlink("empty", ""), Err(VfsError::NotFound));
+
+    // The target is stored as-is, and need not exist.
+    let link = root.clone().lookup("link").unwrap();
+    let attr = link.get_attr().unwrap();
+    assert_eq!(attr.file_type(), VfsNodeType::SymLink);
+    assert_eq!(attr.size(), 11);
+    let node = link.as_any().downcast_ref::<SymlinkNode>().unwrap();
+    assert_eq!(node.target(), "/some/where");
+    assert_eq!(
+        root.clone().lookup("link/x").err(),
+        Some(VfsError::NotADirectory)
+    );
+    assert_eq!(ramfs_memory_usage(), base + 4 + 11);
+
+    drop(link);
+    root.remove("link").unwrap();
+    assert_eq!(ramfs_memory_usage(), base);
+}
@@ -5,12 +5,55 @@
 #[cfg(feature = "axstd")]
 extern crate axstd as std;
 
-use std::new_collections::HashMap;
+mod tests;
+
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::new_collections::{
+    AxRandomState, FnvBuildHasher, HashMap, HashSet, RandomSource, TryReserveError,
+};
+use std::string::String;
+use std::vec::Vec;
+use tests::ConstHasher;
 
 #[cfg_attr(feature = "axstd", no_mangle)]
 fn main() {
     println!("Running memory tests...");
+    tests::run();
     test_hashmap();
+    test_hashmap_with_seed();
+    test_hashmap_iter_len();
+    test_hashmap_index();
+    test_hashmap_get_or_insert_with();
+    test_hashmap_load_factor();
+    test_hashmap_high_bits();
+    test_hashmap_append();
+    test_hashmap_fnv();
+    test_hashmap_bucket_stats();
+    test_hashmap_extract_if();
+    test_hashmap_entry_ref();
+    test_hashmap_default();
+    test_hashmap_zero_rng();
+    test_hashmap_rng_source();
+    test_hashmap_hasher();
+    test_hashmap_remove_entry();
+    test_hashmap_insert_many();
+    test_hashmap_into_keys_values();
+    test_hashmap_from_array();
+    test_hashmap_shrink_to();
+    test_hashset();
+    test_hashmap_retain_map_values();
+    test_hashmap_any_entry();
+    test_hashmap_get_many_mut();
+    test_hashmap_with_hasher();
+    test_hashmap_rehash_all();
+    test_hashmap_try_reserve();
+    test_hashmap_iter_sorted();
+    test_hashmap_max_probe();
+    test_hashmap_insert_fresh();
+    test_hashmap_clone_from();
+    test_hashmap_get_mut_promote();
     println!("Memory tests run OK!");
 }
 
@@ -28,3 +71,850 @@ fn test_hashmap() {
     }
     println!("test_hashmap() OK!");
 }
+
+fn test_hashmap_with_seed() {
+    const N: u32 = 1_000;
+    let mut m1 = HashMap::with_capacity_and_hasher(16, AxRandomState::with_seed(42));
+    let mut m2 = HashMap::with_capacity_and_hasher(16, AxRandomState::with_seed(42));
+    for value in 0..N {
+        m1.insert(value, value * 2);
+        m2.insert(value, value * 2);
+    }
+    let v1: Vec<_> = m1.iter().collect();
+    let v2: Vec<_> = m2.iter().collect();
+    assert_eq!(v1, v2);
+    assert_eq!(m1.get(&7), Some(&14));
+    println!("test_hashmap_with_seed() OK!");
+}
+
+fn test_hashmap_iter_len() {
+    let mut m = HashMap::new();
+    for value in 0..100u32 {
+        m.insert(value, value);
+    }
+    let mut iter = m.iter();
+    assert_eq!(iter.len(), m.len());
+    for remaining in (0..m.len()).rev() {
+        assert!(iter.next().is_some());
+        assert_eq!(iter.len(), remaining);
+        assert_eq!(iter.size_hint(), (remaining, Some(remaining)));
+    }
+    assert!(iter.next().is_none());
+    assert!(iter.next().is_none());
+    println!("test_hashmap_iter_len() OK!");
+}
+
+fn test_hashmap_index() {
+    let mut m = HashMap::new();
+    m.insert(format!("alpha"), 1);
+    m.insert(format!("beta"), 2);
+    assert_eq!(m["alpha"], 1);
+    assert_eq!(m[&format!("beta")], 2);
+    println!("test_hashmap_index() OK!");
+}
+
+fn test_hashmap_get_or_insert_with() {
+    let mut m = HashMap::new();
+    let mut calls = 0;
+    *m.get_or_insert_with(format!("key"), || {
+        calls += 1;
+        1
+    }) += 10;
+    *m.get_or_insert_with(format!("key"), || {
+        calls += 1;
+        1
+    }) += 10;
+    assert_eq!(calls, 1);
+    assert_eq!(m["key"], 21);
+
+    // 触发扩容的插入也要返回正确的引用
+    for value in 0..100u32 {
+        *m.get_or_insert_with(format!("key_{value}"), || value) *= 2;
+    }
+    for value in 0..100u32 {
+        assert_eq!(m[&format!("key_{value}")], value * 2);
+    }
+    assert_eq!(m.len(), 101);
+    println!("test_hashmap_get_or_insert_with() OK!");
+}
+
+fn test_hashmap_load_factor() {
+    // 插入前元素数 / 桶数超过阈值时扩容
+    let mut m = HashMap::with_load_factor(0.5);
+    for value in 0..5u32 {
+        m.insert(value, value);
+    }
+    assert_eq!(m.capacity(), 8);
+    m.insert(5, 5);
+    assert_eq!(m.capacity(), 16);
+
+    let mut m = HashMap::with_load_factor(0.9);
+    for value in 0..8u32 {
+        m.insert(value, value);
+    }
+    assert_eq!(m.capacity(), 8);
+    m.insert(8, 8);
+    assert_eq!(m.capacity(), 16);
+    for value in 0..9u32 {
+        assert_eq!(m[&value], value);
+    }
+    println!("test_hashmap_load_factor() OK!");
+}
+
+/// 直接把整数作为哈希值，不做任何混淆。
+#[derive(Default)]
+struct IdentityHasher(u64);
+
+impl Hasher for IdentityHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 << 8) | b as u64;
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
+fn test_hashmap_high_bits() {
+    // 这些键的低 40 位全部相同，只取低位时会全部落在同一个桶里
+    const N: u64 = 1_000;
+    let mut m =
+        HashMap::with_capacity_and_hasher(16, BuildHasherDefault::<IdentityHasher>::default());
+    for value in 0..N {
+        m.insert(value << 40, value);
+    }
+    assert!(m.max_bucket_len() <= 10);
+    for value in 0..N {
+        assert_eq!(m[&(value << 40)], value);
+    }
+    println!("test_hashmap_high_bits() OK!");
+}
+
+fn test_hashmap_append() {
+    let mut base = HashMap::new();
+    let mut layer = HashMap::new();
+    for value in 0..100u32 {
+        base.insert(value, format!("base_{value}"));
+    }
+    for value in 50..150u32 {
+        layer.insert(value, format!("layer_{value}"));
+    }
+    base.append(&mut layer);
+    assert_eq!(base.len(), 150);
+    assert!(layer.is_empty());
+    assert_eq!(layer.iter().count(), 0);
+    for value in 0..150u32 {
+        let expected = if value < 50 {
+            format!("base_{value}")
+        } else {
+            format!("layer_{value}")
+        };
+        assert_eq!(base[&value], expected);
+    }
+    // 清空后的表仍然可以继续使用
+    layer.insert(1, format!("again"));
+    assert_eq!(layer[&1], "again");
+    println!("test_hashmap_append() OK!");
+}
+
+fn test_hashmap_fnv() {
+    let mut m1 = HashMap::fnv();
+    let mut m2 = HashMap::fnv();
+    for value in 0..1_000u32 {
+        m1.insert(format!("key_{value}"), value);
+        m2.insert(format!("key_{value}"), value);
+    }
+    let v1: Vec<_> = m1.iter().collect();
+    let v2: Vec<_> = m2.iter().collect();
+    assert_eq!(v1, v2);
+    assert_eq!(m1["key_42"], 42);
+    println!("test_hashmap_fnv() OK!");
+}
+
+fn test_hashmap_bucket_stats() {
+    const N: u32 = 1_000;
+    let mut m = HashMap::new();
+    assert_eq!(m.max_bucket_len(), 0);
+    for value in 0..N {
+        m.insert(value, value);
+    }
+    assert_eq!(m.bucket_lengths().count(), m.capacity());
+    assert_eq!(m.bucket_lengths().sum::<usize>(), N as usize);
+    assert!(m.max_bucket_len() <= 10);
+    assert!(m.load_factor() > 0.0 && m.load_factor() <= 0.75);
+    println!("test_hashmap_bucket_stats() OK!");
+}
+
+fn test_hashmap_extract_if() {
+    let mut m = HashMap::new();
+    for value in 0..100u32 {
+        m.insert(value, value);
+    }
+    let mut odds: Vec<_> = m.extract_if(|_, v| *v % 2 == 1).collect();
+    odds.sort();
+    assert_eq!(odds.len(), 50);
+    assert!(odds.iter().all(|&(k, v)| k == v && v % 2 == 1));
+    assert_eq!(m.len(), 50);
+    assert_eq!(m.iter().count(), 50);
+    assert!(m.iter().all(|(_, v)| v % 2 == 0));
+    assert_eq!(m.get(&3), None);
+    assert_eq!(m[&4], 4);
+
+    // 提前丢弃迭代器时，剩下的元素保留在表中
+    let first = m.extract_if(|_, _| true).next();
+    assert!(first.is_some());
+    assert_eq!(m.len(), 49);
+    println!("test_hashmap_extract_if() OK!");
+}
+
+static TOKEN_ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+// 记录从 &str 构造的次数，用来确认已存在的键不会被重新分配
+#[derive(PartialEq, Eq, Hash)]
+struct Token(String);
+
+impl From<&str> for Token {
+    fn from(s: &str) -> Self {
+        TOKEN_ALLOCS.fetch_add(1, Ordering::Relaxed);
+        Token(String::from(s))
+    }
+}
+
+impl Borrow<str> for Token {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+fn test_hashmap_entry_ref() {
+    let tokens = ["a", "b", "a", "c", "b", "a"];
+    let mut m: HashMap<Token, u32> = HashMap::new();
+    for &token in tokens.iter() {
+        *m.entry_ref(token).or_insert(0) += 1;
+    }
+    assert_eq!(TOKEN_ALLOCS.load(Ordering::Relaxed), 3);
+    assert_eq!(m.len(), 3);
+    assert_eq!(m["a"], 3);
+    assert_eq!(m["b"], 2);
+    assert_eq!(m["c"], 1);
+
+    // String 键可以直接用 &str 计数
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for value in 0..100u32 {
+        let key = format!("key_{}", value % 10);
+        *counts.entry_ref(key.as_str()).or_default() += 1;
+    }
+    assert_eq!(counts.len(), 10);
+    assert!(counts.iter().all(|(_, &v)| v == 10));
+    assert!(!counts.entry_ref("missing").is_occupied());
+    println!("test_hashmap_entry_ref() OK!");
+}
+
+fn test_hashmap_default() {
+    #[derive(Default)]
+    struct Registry {
+        names: HashMap<u32, String>,
+        fnv: HashMap<u32, u32, FnvBuildHasher>,
+    }
+
+    let mut m: HashMap<u32, u32> = Default::default();
+    assert!(m.is_empty());
+    m.insert(1, 2);
+    assert_eq!(m[&1], 2);
+
+    let mut r = Registry::default();
+    r.names.insert(7, format!("seven"));
+    r.fnv.insert(7, 7);
+    assert_eq!(r.names[&7], "seven");
+    assert_eq!(r.fnv.len(), 1);
+    println!("test_hashmap_default() OK!");
+}
+
+fn test_hashmap_zero_rng() {
+    // 模拟尚未播种、总是返回 0 的随机源
+    let mut m1 = HashMap::with_capacity_and_hasher(0, AxRandomState::with_rng(&mut || 0));
+    let mut m2 = HashMap::with_capacity_and_hasher(0, AxRandomState::with_rng(&mut || 0));
+    for value in 0..100u32 {
+        m1.insert(value, value);
+        m2.insert(value, value);
+    }
+    let keys1: Vec<_> = m1.iter().map(|(k, _)| *k).collect();
+    let keys2: Vec<_> = m2.iter().map(|(k, _)| *k).collect();
+    assert_ne!(keys1, keys2);
+
+    // 重复返回同一个值的随机源也一样
+    let s1 = AxRandomState::with_rng(&mut || 42);
+    let s2 = AxRandomState::with_rng(&mut || 42);
+    assert_ne!(s1.hash_one(1u32), s2.hash_one(1u32));
+    println!("test_hashmap_zero_rng() OK!");
+}
+
+// 依次返回 next, next + 1, ... 的确定性随机源
+struct CountingRng {
+    next: u64,
+}
+
+impl RandomSource for CountingRng {
+    fn next_u64(&mut self) -> u64 {
+        self.next += 1;
+        self.next - 1
+    }
+}
+
+fn test_hashmap_rng_source() {
+    let mut rng = CountingRng { next: 1000 };
+    let states: Vec<_> = (0..3).map(|_| AxRandomState::with_rng(&mut rng)).collect();
+    // 每个实例只取一个随机数，种子就是取到的值
+    assert_eq!(rng.next, 1003);
+    for (seed, state) in (1000..).zip(states.iter()) {
+        let expected = AxRandomState::with_seed(seed);
+        assert_eq!(state.hash_one("key"), expected.hash_one("key"));
+    }
+
+    let mut m = HashMap::with_capacity_and_hasher(0, AxRandomState::with_rng(&mut rng));
+    m.insert(1, 1);
+    assert_eq!(m[&1], 1);
+    assert_eq!(rng.next, 1004);
+    println!("test_hashmap_rng_source() OK!");
+}
+
+fn test_hashmap_hasher() {
+    let mut m1 = HashMap::with_capacity_and_hasher(0, AxRandomState::with_seed(7));
+    for value in 0..100u32 {
+        m1.insert(value, value);
+    }
+    // 用同一个哈希状态构建的表，桶布局和迭代顺序完全一致
+    let mut m2 = HashMap::with_capacity_and_hasher(0, m1.hasher().clone());
+    for value in 0..100u32 {
+        m2.insert(value, value * 2);
+    }
+    assert_eq!(m1.hasher().hash_one(42u32), m2.hasher().hash_one(42u32));
+    let keys1: Vec<_> = m1.iter().map(|(k, _)| *k).collect();
+    let keys2: Vec<_> = m2.iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys1, keys2);
+    println!("test_hashmap_hasher() OK!");
+}
+
+// 忽略大小写比较的字符串，用来通过 Borrow 以不同的大小写查找
+#[repr(transparent)]
+struct Uncased(str);
+
+impl Uncased {
+    fn new(s: &str) -> &Uncased {
+        // Uncased 与 str 的内存布局相同
+        unsafe { &*(s as *const str as *const Uncased) }
+    }
+}
+
+impl PartialEq for Uncased {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Eq for Uncased {}
+
+impl Hash for Uncased {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.0.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+    }
+}
+
+// 存储时统一转成小写的键
+struct NormalizedKey(String);
+
+impl NormalizedKey {
+    fn new(s: &str) -> Self {
+        NormalizedKey(s.to_ascii_lowercase())
+    }
+}
+
+impl Borrow<Uncased> for NormalizedKey {
+    fn borrow(&self) -> &Uncased {
+        Uncased::new(&self.0)
+    }
+}
+
+impl PartialEq for NormalizedKey {
+    fn eq(&self, other: &Self) -> bool {
+        Borrow::<Uncased>::borrow(self) == Borrow::<Uncased>::borrow(other)
+    }
+}
+
+impl Eq for NormalizedKey {}
+
+impl Hash for NormalizedKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Borrow::<Uncased>::borrow(self).hash(state)
+    }
+}
+
+fn test_hashmap_remove_entry() {
+    let mut m = HashMap::new();
+    m.insert(NormalizedKey::new("Alpha"), 1);
+    m.insert(NormalizedKey::new("Beta"), 2);
+    let (key, value) = m.remove_entry(Uncased::new("ALPHA")).unwrap();
+    assert_eq!(key.0, "alpha");
+    assert_eq!(value, 1);
+    assert_eq!(m.len(), 1);
+    assert!(m.remove_entry(Uncased::new("alpha")).is_none());
+    assert_eq!(m.remove(Uncased::new("bEtA")), Some(2));
+    assert!(m.is_empty());
+    println!("test_hashmap_remove_entry() OK!");
+}
+
+static KEY_COMPARES: AtomicUsize = AtomicUsize::new(0);
+
+// 记录键比较的次数，用来确认插入时扫描的链不会越来越长
+#[derive(Hash, Eq)]
+struct CountedKey(u32);
+
+impl PartialEq for CountedKey {
+    fn eq(&self, other: &Self) -> bool {
+        KEY_COMPARES.fetch_add(1, Ordering::Relaxed);
+        self.0 == other.0
+    }
+}
+
+fn test_hashmap_insert_many() {
+    const N: u32 = 10_000;
+    let mut m1 = HashMap::new();
+    for value in 0..N {
+        m1.insert(value, value);
+    }
+    let mut m2 = HashMap::new();
+    m2.insert_many((0..N).map(|v| (v, v)));
+    // 逐个插入会反复扩容，批量插入只在开始时预留一次
+    assert!(m1.resize_count() > 5);
+    assert_eq!(m2.resize_count(), 1);
+    assert_eq!(m2.len(), N as usize);
+    assert_eq!(m2.capacity(), m1.capacity());
+    assert!(m2.load_factor() <= 0.75);
+    for value in 0..N {
+        assert_eq!(m2[&value], value);
+    }
+
+    // size_hint 的下界为 0 时，插入过程中负载因子不超过阈值的两倍，
+    // 链始终很短，比较次数与元素个数成线性关系；最后再按阈值扩容
+    let mut m3 = HashMap::new();
+    KEY_COMPARES.store(0, Ordering::Relaxed);
+    m3.insert_many((0..N).filter(|_| true).map(|v| (CountedKey(v), v)));
+    assert!(KEY_COMPARES.load(Ordering::Relaxed) < 4 * N as usize);
+    assert!(m3.resize_count() > 1);
+    assert!(m3.load_factor() <= 0.75);
+    assert_eq!(m3.len(), N as usize);
+
+    // 已有的键被覆盖
+    m3.insert_many([(CountedKey(1), 100)]);
+    assert_eq!(m3[&CountedKey(1)], 100);
+    assert_eq!(m3.len(), N as usize);
+    println!("test_hashmap_insert_many() OK!");
+}
+
+fn test_hashmap_into_keys_values() {
+    let mut m = HashMap::new();
+    for value in 0..100u32 {
+        m.insert(format!("key_{value:03}"), value);
+    }
+
+    let mut keys: Vec<String> = m.into_keys().collect();
+    keys.sort();
+    let expected: Vec<String> = (0..100u32).map(|v| format!("key_{v:03}")).collect();
+    assert_eq!(keys, expected);
+
+    let mut m = HashMap::new();
+    for value in 0..100u32 {
+        m.insert(format!("key_{value:03}"), value);
+    }
+    let values = m.into_values();
+    assert_eq!(values.len(), 100);
+    let mut values: Vec<u32> = values.collect();
+    values.sort();
+    assert_eq!(values, (0..100).collect::<Vec<_>>());
+    println!("test_hashmap_into_keys_values() OK!");
+}
+
+fn test_hashmap_from_array() {
+    let m = HashMap::from([("a", 1), ("b", 2)]);
+    assert_eq!(m.len(), 2);
+    assert_eq!(m.get("a"), Some(&1));
+    assert_eq!(m.get("b"), Some(&2));
+    assert_eq!(m.get("c"), None);
+
+    // 预先按元素个数分配好桶，构建大的表也不需要扩容
+    let mut arr = [(0u32, 0u32); 500];
+    for (i, pair) in arr.iter_mut().enumerate() {
+        *pair = (i as u32, i as u32 * 2);
+    }
+    let m = HashMap::from(arr);
+    assert_eq!(m.len(), 500);
+    assert_eq!(m.resize_count(), 0);
+    assert!((0..500).all(|i| m.get(&i) == Some(&(i * 2))));
+    println!("test_hashmap_from_array() OK!");
+}
+
+fn test_hashmap_shrink_to() {
+    let mut m = HashMap::new();
+    for i in 0..2000u32 {
+        m.insert(i, i + 1);
+    }
+    let capacity = m.capacity();
+    assert!(capacity >= 2048);
+    for i in 20..2000u32 {
+        m.remove(&i);
+    }
+
+    // 要求的桶数比现有的多时不会扩容
+    m.shrink_to(capacity * 2);
+    assert_eq!(m.capacity(), capacity);
+
+    m.shrink_to(64);
+    assert_eq!(m.capacity(), 64);
+    assert_eq!(m.len(), 20);
+    assert!((0..20u32).all(|i| m.get(&i) == Some(&(i + 1))));
+
+    // 缩小后负载因子仍在阈值以下，桶数不低于初始容量
+    m.shrink_to(0);
+    assert_eq!(m.capacity(), 32);
+    for i in 0..20u32 {
+        m.remove(&i);
+    }
+    m.shrink_to(0);
+    assert_eq!(m.capacity(), 8);
+    println!("test_hashmap_shrink_to() OK!");
+}
+
+fn test_hashset() {
+    let input = [3u32, 1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 8, 9, 7, 9];
+    let mut set = HashSet::new();
+    let mut unique = Vec::new();
+    for &n in input.iter() {
+        if set.insert(n) {
+            unique.push(n);
+        }
+    }
+    assert_eq!(unique, [3, 1, 4, 5, 9, 2, 6, 8, 7]);
+    assert_eq!(set.len(), 9);
+    assert!(set.contains(&4));
+    assert!(!set.contains(&0));
+
+    let mut items: Vec<u32> = set.iter().copied().collect();
+    items.sort();
+    assert_eq!(items, [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+    assert!(set.remove(&4));
+    assert!(!set.remove(&4));
+    assert!(!set.contains(&4));
+    assert_eq!(set.iter().len(), 8);
+
+    // 和 HashMap 一样可以用借用的键查找
+    let mut names = HashSet::new();
+    names.insert(String::from("alice"));
+    assert!(names.contains("alice"));
+    assert!(!names.insert(String::from("alice")));
+    println!("test_hashset() OK!");
+}
+
+fn test_hashmap_retain_map_values() {
+    let mut m = HashMap::new();
+    for i in 0..100u32 {
+        m.insert(i, i);
+    }
+    m.retain_keys(|&k| k % 3 == 0);
+    assert_eq!(m.len(), 34);
+    assert_eq!(m.iter().count(), 34);
+    assert!((0..100u32).all(|k| m.get(&k).is_some() == (k % 3 == 0)));
+
+    // 值转换后键全部保留，哈希状态沿用，因此按键查找仍然有效
+    let mut raw = HashMap::new();
+    for i in 0..50u32 {
+        raw.insert(format!("opt{i}"), format!("{}", i * 10));
+    }
+    let parsed: HashMap<String, u32> = raw.map_values(|v| v.parse().unwrap());
+    assert_eq!(parsed.len(), 50);
+    for i in 0..50u32 {
+        assert_eq!(parsed.get(format!("opt{i}").as_str()), Some(&(i * 10)));
+    }
+    println!("test_hashmap_retain_map_values() OK!");
+}
+
+fn test_hashmap_any_entry() {
+    let mut m: HashMap<u32, u32> = HashMap::new();
+    assert!(m.any_entry().is_none());
+    assert!(m.pop_any().is_none());
+
+    for i in 0..20u32 {
+        m.insert(i, i * 2);
+    }
+    let (&k, &v) = m.any_entry().unwrap();
+    assert_eq!(m.get(&k), Some(&v));
+
+    // 每次弹出的都是表中真实存在的键值对，且 len 减一
+    let mut popped = 0;
+    while let Some((k, v)) = m.pop_any() {
+        popped += 1;
+        assert_eq!(v, k * 2);
+        assert!(m.get(&k).is_none());
+        assert_eq!(m.len(), 20 - popped);
+    }
+    assert_eq!(popped, 20);
+    assert!(m.is_empty());
+    assert!(m.any_entry().is_none());
+    println!("test_hashmap_any_entry() OK!");
+}
+
+fn test_hashmap_get_many_mut() {
+    let mut m = HashMap::new();
+    for i in 0..20u32 {
+        m.insert(format!("k{i}"), i);
+    }
+    // 成功：同时修改两个值
+    let [a, b] = m.get_many_mut(["k3", "k17"]).unwrap();
+    core::mem::swap(a, b);
+    *a += 100;
+    assert_eq!(m.get("k3"), Some(&117));
+    assert_eq!(m.get("k17"), Some(&3));
+
+    // 重复的键会导致可变引用重叠，应当拒绝
+    assert!(m.get_many_mut(["k1", "k2", "k1"]).is_none());
+    // 任意一个键不存在时返回 None
+    assert!(m.get_many_mut(["k1", "missing"]).is_none());
+    assert_eq!(m.get("k1"), Some(&1));
+
+    let mut empty: HashMap<u32, u32> = HashMap::new();
+    assert!(empty.get_many_mut([&1]).is_none());
+    println!("test_hashmap_get_many_mut() OK!");
+}
+
+fn test_hashmap_with_hasher() {
+    let mut m = HashMap::with_hasher(AxRandomState::with_seed(7));
+    assert!(m.is_empty());
+    m.insert("seven", 7);
+    assert_eq!(m.get("seven"), Some(&7));
+    assert!(m.get("eight").is_none());
+
+    // 种子相同，哈希结果一致
+    let other = AxRandomState::with_seed(7);
+    assert_eq!(m.hasher().hash_one("seven"), other.hash_one("seven"));
+    println!("test_hashmap_with_hasher() OK!");
+}
+
+fn test_hashmap_rehash_all() {
+    const CAP: usize = 1024;
+    let state = AxRandomState::with_seed(0x5eed);
+    // 单个键插入容量相同、种子相同的空表，由非空桶的位置得知它落在哪个桶
+    let mut probe = HashMap::with_capacity_and_hasher(CAP, state.clone());
+    let mut bucket_of = |key: u32| {
+        probe.insert(key, ());
+        let index = probe.bucket_lengths().position(|len| len == 1).unwrap();
+        probe.remove(&key);
+        index
+    };
+    // 挑出 20 个落在同一个桶里的键，人为制造退化的分布
+    let target = bucket_of(0);
+    let keys: Vec<u32> = (0..).filter(|&k| bucket_of(k) == target).take(20).collect();
+    let mut m = HashMap::with_capacity_and_hasher(CAP, state.clone());
+    for &k in &keys {
+        m.insert(k, k * 3);
+    }
+    assert_eq!(m.max_bucket_len(), 20);
+
+    m.rehash_all();
+    assert!(m.max_bucket_len() < 20);
+    assert_eq!(m.capacity(), CAP);
+    assert_eq!(m.len(), 20);
+    assert_eq!(m.resize_count(), 0);
+    for &k in &keys {
+        assert_eq!(m.get(&k), Some(&(k * 3)));
+    }
+    println!("test_hashmap_rehash_all() OK!");
+}
+
+fn test_hashmap_try_reserve() {
+    let mut m = HashMap::new();
+    for i in 0..10u32 {
+        m.insert(i, i);
+    }
+    let capacity = m.capacity();
+
+    // 容量溢出和内存不足都返回错误，表保持不变
+    assert_eq!(m.try_reserve(usize::MAX), Err(TryReserveError::CapacityOverflow));
+    assert!(matches!(m.try_reserve(1 << 40), Err(TryReserveError::AllocError { .. })));
+    assert_eq!(m.capacity(), capacity);
+    assert_eq!(m.len(), 10);
+    assert!((0..10u32).all(|i| m.get(&i) == Some(&i)));
+
+    // 合理的请求照常预留
+    m.try_reserve(100).unwrap();
+    assert!(m.capacity() as f32 * 0.75 >= 110.0);
+    let resizes = m.resize_count();
+    for i in 10..110u32 {
+        m.insert(i, i);
+    }
+    assert_eq!(m.resize_count(), resizes);
+    println!("test_hashmap_try_reserve() OK!");
+}
+
+fn test_hashmap_iter_sorted() {
+    let mut m1 = HashMap::new();
+    let mut m2 = HashMap::new();
+    for i in 0..50u32 {
+        m1.insert(i * 7 % 50, i);
+    }
+    for i in (0..50u32).rev() {
+        m2.insert(i * 7 % 50, i);
+    }
+    // 两个表的种子不同、插入顺序不同，排序后的结果仍然一致
+    let sorted = m1.iter_sorted();
+    assert_eq!(sorted, m2.iter_sorted());
+    assert_eq!(sorted.len(), 50);
+    assert!(sorted.windows(2).all(|w| w[0].0 < w[1].0));
+    assert!(sorted.iter().all(|&(k, v)| v * 7 % 50 == *k));
+    assert!(HashMap::<u32, u32>::new().iter_sorted().is_empty());
+    println!("test_hashmap_iter_sorted() OK!");
+}
+
+fn test_hashmap_max_probe() {
+    // 所有键都落在同一条链上。filter 的 size_hint 下限是 0，insert_many 不会预留空间，
+    // 链长每次达到桶数时都要先扩容：16 -> 32 -> 64 -> 128，最后按负载因子再扩容到 256
+    const N: u32 = 100;
+    let mut m =
+        HashMap::with_capacity_and_hasher(16, BuildHasherDefault::<ConstHasher>::default());
+    m.insert_many((0..N).filter(|_| true).map(|value| (value, value)));
+    assert_eq!(m.resize_count(), 4);
+    assert_eq!(m.capacity(), 256);
+    assert_eq!(m.max_bucket_len(), N as usize);
+    for value in 0..N {
+        assert_eq!(m[&value], value);
+    }
+
+    // 已存在的键原地更新，链满了也不会扩容
+    let mut m =
+        HashMap::with_capacity_and_hasher(16, BuildHasherDefault::<ConstHasher>::default());
+    m.insert_many((0..16u32).filter(|_| true).map(|value| (value, value)));
+    assert_eq!(m.resize_count(), 1);
+    m.insert_many((0..16u32).filter(|_| true).map(|value| (value, value + 1)));
+    assert_eq!(m.resize_count(), 1);
+    assert!((0..16u32).all(|value| m[&value] == value + 1));
+
+    // get_or_insert_with 和 entry_ref 插入时链长同样不超过桶数
+    let mut m =
+        HashMap::with_capacity_and_hasher(16, BuildHasherDefault::<ConstHasher>::default());
+    for value in 0..N {
+        *m.get_or_insert_with(value, || 0) += value;
+        assert!(m.max_bucket_len() <= m.capacity());
+    }
+    assert!((0..N).all(|value| m[&value] == value));
+    let mut counts: HashMap<String, u32, _> =
+        HashMap::with_capacity_and_hasher(16, BuildHasherDefault::<ConstHasher>::default());
+    for value in 0..N {
+        *counts.entry_ref(format!("key_{value}").as_str()).or_insert(0) += 1;
+        assert!(counts.max_bucket_len() <= counts.capacity());
+    }
+    assert_eq!(counts.len(), N as usize);
+    println!("test_hashmap_max_probe() OK!");
+}
+
+fn test_hashmap_insert_fresh() {
+    // 各种方式新建的表都带着初始的桶，第一次插入不需要扩容
+    let mut m1 = HashMap::new();
+    let mut m2 = HashMap::fnv();
+    let mut m3: HashMap<u32, u32> = HashMap::default();
+    let mut m4 = HashMap::with_hasher(AxRandomState::with_seed(1));
+    for m in [&mut m1, &mut m3, &mut m4] {
+        assert_eq!(m.insert(1, 10), None);
+        assert_eq!(m.insert(1, 11), Some(10));
+        assert_eq!(m.resize_count(), 0);
+    }
+    assert_eq!(m2.insert(1, 10), None);
+    assert_eq!(m2.resize_count(), 0);
+
+    // 预留之后再逐个插入，insert 不会再扩容
+    let mut m = HashMap::new();
+    m.reserve(100);
+    let resizes = m.resize_count();
+    for i in 0..100u32 {
+        m.insert(i, i);
+    }
+    assert_eq!(m.resize_count(), resizes);
+    assert_eq!(m.len(), 100);
+    m.insert(100, 100);
+    assert!((0..=100u32).all(|i| m.get(&i) == Some(&i)));
+    println!("test_hashmap_insert_fresh() OK!");
+}
+
+fn test_hashmap_clone_from() {
+    let state = AxRandomState::with_seed(0x5eed);
+    let mut src = HashMap::with_capacity_and_hasher(64, state.clone());
+    let mut dst = HashMap::with_capacity_and_hasher(64, state.clone());
+    for i in 0..40u32 {
+        src.insert(i, i * 2);
+        dst.insert(i, i);
+    }
+
+    // 桶数相同：链原地覆盖，每个值的地址不变，说明没有重新分配
+    let before: Vec<*const u32> = (0..40u32).map(|i| &dst[&i] as *const u32).collect();
+    dst.clone_from(&src);
+    let after: Vec<*const u32> = (0..40u32).map(|i| &dst[&i] as *const u32).collect();
+    assert_eq!(before, after);
+    assert_eq!(dst.iter_sorted(), src.iter_sorted());
+    assert_eq!(dst.capacity(), 64);
+
+    // 键集不同也能得到相同的结果
+    src.insert(100, 200);
+    src.remove(&0);
+    dst.clone_from(&src);
+    assert_eq!(dst.iter_sorted(), src.iter_sorted());
+    assert_eq!(dst.get(&0), None);
+
+    // 桶数不同：退回到重新克隆
+    let mut small = HashMap::with_capacity_and_hasher(8, state);
+    small.insert(7, 7);
+    small.clone_from(&src);
+    assert_eq!(small.iter_sorted(), src.iter_sorted());
+    assert_eq!(small.capacity(), src.capacity());
+
+    let cloned = src.clone();
+    assert_eq!(cloned.iter_sorted(), src.iter_sorted());
+    println!("test_hashmap_clone_from() OK!");
+}
+
+fn test_hashmap_get_mut_promote() {
+    // 所有键都在同一条链上，迭代顺序就是链内的顺序
+    let mut m =
+        HashMap::with_capacity_and_hasher(16, BuildHasherDefault::<ConstHasher>::default());
+    for i in 0..10u32 {
+        m.insert(i, i);
+    }
+    let order = |m: &HashMap<u32, u32, _>| m.iter().map(|(&k, _)| k).collect::<Vec<_>>();
+    assert_eq!(order(&m), (0..10).collect::<Vec<_>>());
+
+    // 链尾的键被移到链头，其余的键保持原来的相对顺序
+    *m.get_mut_promote(&9).unwrap() += 100;
+    assert_eq!(order(&m), [9, 0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(m[&9], 109);
+    m.get_mut_promote(&5).unwrap();
+    assert_eq!(order(&m), [5, 9, 0, 1, 2, 3, 4, 6, 7, 8]);
+    // 已经在链头时顺序不变
+    m.get_mut_promote(&5).unwrap();
+    assert_eq!(order(&m)[0], 5);
+
+    assert!(m.get_mut_promote(&42).is_none());
+    assert_eq!(m.len(), 10);
+    assert!((0..10u32).all(|i| m.get(&i).is_some()));
+
+    // 删除不会把链尾的键换到前面，提升过的键仍留在链头
+    assert_eq!(m.remove(&0), Some(0));
+    assert_eq!(order(&m), [5, 9, 1, 2, 3, 4, 6, 7, 8]);
+    assert_eq!(m.remove(&5), Some(5));
+    assert_eq!(order(&m), [9, 1, 2, 3, 4, 6, 7, 8]);
+    println!("test_hashmap_get_mut_promote() OK!");
+}
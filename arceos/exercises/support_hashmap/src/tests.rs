@@ -0,0 +1,77 @@
+//! HashMap 桶逻辑的基础测试：插入、查找、删除、扩容和冲突。
+//!
+//! `axstd` 是 `no_std` 的，不能在宿主机上用 `#[test]` 运行，
+//! 所以这些测试和 main.rs 中的其余测试一样在目标机上运行。
+
+use core::hash::{BuildHasherDefault, Hasher};
+use std::new_collections::HashMap;
+
+pub fn run() {
+    test_insert_get_remove();
+    test_resize_twice();
+    test_collisions();
+    println!("HashMap bucket tests OK!");
+}
+
+fn test_insert_get_remove() {
+    let mut m = HashMap::new();
+    assert!(m.is_empty());
+    assert_eq!(m.insert(1, "one"), None);
+    assert_eq!(m.insert(2, "two"), None);
+    assert_eq!(m.insert(1, "uno"), Some("one"));
+    assert_eq!(m.len(), 2);
+    assert_eq!(m.get(&1), Some(&"uno"));
+    assert_eq!(m.get(&3), None);
+    assert_eq!(m.remove(&1), Some("uno"));
+    assert_eq!(m.remove(&1), None);
+    assert_eq!(m.get(&1), None);
+    assert_eq!(m.len(), 1);
+}
+
+fn test_resize_twice() {
+    let mut m = HashMap::new();
+    let initial = m.capacity();
+    let mut n = 0u32;
+    while m.capacity() < initial * 4 {
+        m.insert(n, n * 3);
+        n += 1;
+    }
+    assert_eq!(m.resize_count(), 2);
+    assert_eq!(m.len(), n as usize);
+    for key in 0..n {
+        assert_eq!(m.get(&key), Some(&(key * 3)), "key {} lost after resize", key);
+    }
+    assert_eq!(m.iter().count(), n as usize);
+}
+
+/// 所有键的哈希值都相同。
+#[derive(Default)]
+pub(crate) struct ConstHasher;
+
+impl Hasher for ConstHasher {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {}
+}
+
+fn test_collisions() {
+    const N: u32 = 100;
+    let mut m = HashMap::with_capacity_and_hasher(0, BuildHasherDefault::<ConstHasher>::default());
+    for key in 0..N {
+        m.insert(key, key);
+    }
+    // 全部落在同一个桶里，仍然能按键区分
+    assert_eq!(m.max_bucket_len(), N as usize);
+    for key in 0..N {
+        assert_eq!(m.get(&key), Some(&key));
+    }
+    for key in (0..N).step_by(2) {
+        assert_eq!(m.remove(&key), Some(key));
+    }
+    assert_eq!(m.len(), N as usize / 2);
+    for key in 0..N {
+        assert_eq!(m.get(&key), (key % 2 == 1).then_some(&key));
+    }
+}
@@ -11,6 +11,8 @@ extern crate axlog;
 mod task;
 mod syscall;
 mod loader;
+mod shm;
+mod uaccess;
 
 use axstd::io;
 use axhal::paging::MappingFlags;
@@ -25,6 +27,8 @@ use loader::load_user_app;
 
 const USER_STACK_SIZE: usize = 0x10000;
 const KERNEL_STACK_SIZE: usize = 0x40000; // 256 KiB
+const USER_HEAP_BASE: usize = 0x4000_0000;
+const USER_HEAP_SIZE_MAX: usize = 0x100_0000; // 16 MiB
 
 #[cfg_attr(feature = "axstd", no_mangle)]
 fn main() {
@@ -0,0 +1,85 @@
+//! System V 共享内存段
+//!
+//! 每个段是一组 [`SharedFrames`]，由映射它的地址空间和全局段表共同持有。
+//! 任务用 `shmget` 的 key 找到同一个段（`IPC_PRIVATE` 的段只能通过返回的 id
+//! 共享，例如在 fork 之前创建），再用 `shmat` 把相同的物理页映射进自己的
+//! 地址空间。`IPC_RMID` 从表中删除段，最后一个映射撤销时物理页才被释放。
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::paging::MappingFlags;
+use axmm::{AddrSpace, SharedFrames};
+use axsync::Mutex;
+use memory_addr::PAGE_SIZE_4K;
+
+pub const IPC_PRIVATE: i32 = 0;
+pub const IPC_CREAT: i32 = 0o1000;
+pub const IPC_EXCL: i32 = 0o2000;
+
+struct Segment {
+    key: i32,
+    frames: Arc<SharedFrames>,
+}
+
+static SEGMENTS: Mutex<BTreeMap<usize, Segment>> = Mutex::new(BTreeMap::new());
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// 按 `shmget` 的规则查找或创建 key 对应的段，返回段的 id
+pub fn get(key: i32, size: usize, flags: i32) -> LinuxResult<usize> {
+    let mut segments = SEGMENTS.lock();
+    if key != IPC_PRIVATE {
+        if let Some((&id, seg)) = segments.iter().find(|(_, seg)| seg.key == key) {
+            if flags & IPC_CREAT != 0 && flags & IPC_EXCL != 0 {
+                return Err(LinuxError::EEXIST);
+            }
+            if size > seg.frames.size() {
+                return Err(LinuxError::EINVAL);
+            }
+            return Ok(id);
+        }
+        if flags & IPC_CREAT == 0 {
+            return Err(LinuxError::ENOENT);
+        }
+    }
+    if size == 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let num_pages = size
+        .checked_next_multiple_of(PAGE_SIZE_4K)
+        .ok_or(LinuxError::EINVAL)?
+        / PAGE_SIZE_4K;
+    let frames = SharedFrames::new(num_pages).map_err(|_| LinuxError::ENOMEM)?;
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    segments.insert(id, Segment { key, frames });
+    Ok(id)
+}
+
+/// 返回 id 对应的段的大小
+pub fn size(id: usize) -> LinuxResult<usize> {
+    let segments = SEGMENTS.lock();
+    let seg = segments.get(&id).ok_or(LinuxError::EINVAL)?;
+    Ok(seg.frames.size())
+}
+
+/// 把 id 对应的段映射到 `uspace` 的 `vaddr` 处，返回段的大小
+pub fn attach(id: usize, uspace: &mut AddrSpace, vaddr: usize, flags: MappingFlags) -> LinuxResult<usize> {
+    let frames = SEGMENTS
+        .lock()
+        .get(&id)
+        .map(|seg| seg.frames.clone())
+        .ok_or(LinuxError::EINVAL)?;
+    let size = frames.size();
+    uspace
+        .map_shared(vaddr.into(), frames, flags)
+        .map_err(|_| LinuxError::ENOMEM)?;
+    Ok(size)
+}
+
+/// 从表中删除 id 对应的段，已有的映射不受影响
+pub fn remove(id: usize) -> LinuxResult {
+    SEGMENTS.lock().remove(&id).ok_or(LinuxError::EINVAL)?;
+    Ok(())
+}
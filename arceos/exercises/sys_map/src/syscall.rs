@@ -1,39 +1,137 @@
 #![allow(dead_code)]
 
 use core::ffi::{c_void, c_char, c_int};
-use axhal::arch::TrapFrame;
-use axhal::trap::{register_trap_handler, SYSCALL};
-use axerrno::LinuxError;
+use axhal::arch::{TrapFrame, UspaceContext};
+use axhal::trap::{register_trap_handler, PAGE_FAULT, SYSCALL};
+use axerrno::{LinuxError, LinuxResult};
 use axtask::current;
 use axtask::TaskExtRef;
+use axtask::AxTaskRef;
 use axhal::paging::MappingFlags;
 use arceos_posix_api as api;
 
 // 内存管理相关
-use alloc::vec;
 use alloc::vec::Vec;
-use axhal::mem::{PAGE_SIZE_4K, phys_to_virt};
+use axhal::mem::{PAGE_SIZE_4K, phys_to_virt, VirtAddr};
+use axmm::{AddrSpace, SharedFrames};
+use memory_addr::{align_up_4k, is_aligned_4k, VirtAddrRange};
 use alloc::sync::Arc;
+use axsync::Mutex;
 use arceos_posix_api::imp::fd_ops::{get_file_like, FileLike};
+use crate::shm;
+use crate::task::{spawn_child_task, SharedMapping, TaskExt};
+use crate::uaccess::{check_user_range, copy_from_user, copy_to_user, user_cstr, user_slice_mut};
 
 
 // 文件操作相关（根据你的项目实际情况调整）
 use axstd::fs::File; // 如果你用 axstd 文件系统
+use axstd::io::SeekFrom;
+use alloc::ffi::CString;
+use alloc::format;
+use alloc::string::String;
 // use crate::your_file_table::get_file_by_fd; // 如果有自定义文件表
 
 
+const SYS_GETCWD: usize = 17;
+const SYS_DUP: usize = 23;
+const SYS_DUP3: usize = 24;
+const SYS_FCNTL: usize = 25;
 const SYS_IOCTL: usize = 29;
+const SYS_MKDIRAT: usize = 34;
+const SYS_UNLINKAT: usize = 35;
+const SYS_SYMLINKAT: usize = 36;
+const SYS_RENAMEAT: usize = 38;
+const SYS_STATFS: usize = 43;
+const SYS_FSTATFS: usize = 44;
+const SYS_FTRUNCATE: usize = 46;
+const SYS_FACCESSAT: usize = 48;
+const SYS_CHDIR: usize = 49;
+const SYS_FCHDIR: usize = 50;
 const SYS_OPENAT: usize = 56;
 const SYS_CLOSE: usize = 57;
+const SYS_PIPE2: usize = 59;
+const SYS_GETDENTS64: usize = 61;
+const SYS_LSEEK: usize = 62;
 const SYS_READ: usize = 63;
 const SYS_WRITE: usize = 64;
+const SYS_READV: usize = 65;
 const SYS_WRITEV: usize = 66;
+const SYS_PREAD64: usize = 67;
+const SYS_PWRITE64: usize = 68;
+const SYS_SENDFILE: usize = 71;
+const SYS_PPOLL: usize = 73;
+const SYS_READLINKAT: usize = 78;
+const SYS_FSTAT: usize = 80;
 const SYS_EXIT: usize = 93;
 const SYS_EXIT_GROUP: usize = 94;
 const SYS_SET_TID_ADDRESS: usize = 96;
+const SYS_SET_ROBUST_LIST: usize = 99;
+const SYS_NANOSLEEP: usize = 101;
+const SYS_CLOCK_GETTIME: usize = 113;
+const SYS_RT_SIGPROCMASK: usize = 135;
+const SYS_RT_SIGPENDING: usize = 136;
+const SYS_GETPID: usize = 172;
+const SYS_GETPPID: usize = 173;
+const SYS_GETTID: usize = 178;
+const SYS_SHMGET: usize = 194;
+const SYS_SHMCTL: usize = 195;
+const SYS_SHMAT: usize = 196;
+const SYS_SHMDT: usize = 197;
+const SYS_BRK: usize = 214;
+const SYS_MUNMAP: usize = 215;
+const SYS_CLONE: usize = 220;
 const SYS_MMAP: usize = 222;
+const SYS_MPROTECT: usize = 226;
+const SYS_MSYNC: usize = 227;
+const SYS_WAIT4: usize = 260;
+const SYS_RENAMEAT2: usize = 276;
 
 const AT_FDCWD: i32 = -100;
+const AT_REMOVEDIR: i32 = 0x200;
+const AT_EACCESS: i32 = 0x200;
+const AT_SYMLINK_NOFOLLOW: i32 = 0x100;
+const RENAME_NOREPLACE: u32 = 1 << 0;
+
+const MS_ASYNC: i32 = 1;
+const MS_INVALIDATE: i32 = 2;
+const MS_SYNC: i32 = 4;
+
+const F_OK: i32 = 0;
+const R_OK: i32 = 4;
+const W_OK: i32 = 2;
+const X_OK: i32 = 1;
+
+const WNOHANG: i32 = 1;
+// clone 的 flags 低 8 位是子任务退出时发给父任务的信号
+const CSIGNAL: usize = 0xff;
+
+const SIG_BLOCK: i32 = 0;
+const SIG_UNBLOCK: i32 = 1;
+const SIG_SETMASK: i32 = 2;
+// SIGKILL 和 SIGSTOP 不能被阻塞
+const UNBLOCKABLE_SIGNALS: u64 = (1 << (9 - 1)) | (1 << (19 - 1));
+// struct robust_list_head 的大小：两个指针加一个 long
+const ROBUST_LIST_HEAD_SIZE: usize = 24;
+
+const POLLIN: i16 = 0x001;
+const POLLOUT: i16 = 0x004;
+const POLLNVAL: i16 = 0x020;
+
+const TIOCGWINSZ: usize = 0x5413;
+const FIONBIO: usize = 0x5421;
+
+const MMAP_BASE: usize = 0x8000_0000;
+
+const SHM_RDONLY: i32 = 0o10000;
+const IPC_RMID: i32 = 0;
+// libc 给 shmctl 的 cmd 加上的新版结构体标记
+const IPC_64: i32 = 0x100;
+
+const IOV_MAX: i32 = 1024;
+const SYMLOOP_MAX: usize = 40;
+
+const RAMFS_MAGIC: i64 = 0x8584_58f6;
+const NAME_MAX: i64 = 255;
 
 /// Macro to generate syscall body
 ///
@@ -109,25 +207,197 @@ bitflags::bitflags! {
     }
 }
 
+/// File status as laid out by the generic Linux ABI (used by riscv64).
+///
+/// See <https://github.com/torvalds/linux/blob/master/include/uapi/asm-generic/stat.h>
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct KernelStat {
+    st_dev: u64,
+    st_ino: u64,
+    st_mode: u32,
+    st_nlink: u32,
+    st_uid: u32,
+    st_gid: u32,
+    st_rdev: u64,
+    __pad1: u64,
+    st_size: i64,
+    st_blksize: i32,
+    __pad2: i32,
+    st_blocks: i64,
+    st_atime_sec: i64,
+    st_atime_nsec: i64,
+    st_mtime_sec: i64,
+    st_mtime_nsec: i64,
+    st_ctime_sec: i64,
+    st_ctime_nsec: i64,
+    __unused: [u32; 2],
+}
+
+impl From<api::ctypes::stat> for KernelStat {
+    fn from(st: api::ctypes::stat) -> Self {
+        Self {
+            st_dev: st.st_dev as _,
+            st_ino: st.st_ino as _,
+            st_mode: st.st_mode as _,
+            st_nlink: st.st_nlink as _,
+            st_uid: st.st_uid as _,
+            st_gid: st.st_gid as _,
+            st_rdev: st.st_rdev as _,
+            st_size: st.st_size as _,
+            st_blksize: st.st_blksize as _,
+            st_blocks: st.st_blocks as _,
+            st_atime_sec: st.st_atime.tv_sec as _,
+            st_atime_nsec: st.st_atime.tv_nsec as _,
+            st_mtime_sec: st.st_mtime.tv_sec as _,
+            st_mtime_nsec: st.st_mtime.tv_nsec as _,
+            st_ctime_sec: st.st_ctime.tv_sec as _,
+            st_ctime_nsec: st.st_ctime.tv_nsec as _,
+            ..Default::default()
+        }
+    }
+}
+
+/// Filesystem statistics as laid out by the generic Linux ABI (used by riscv64).
+///
+/// See <https://github.com/torvalds/linux/blob/master/include/uapi/asm-generic/statfs.h>
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct KernelStatfs {
+    f_type: i64,
+    f_bsize: i64,
+    f_blocks: u64,
+    f_bfree: u64,
+    f_bavail: u64,
+    f_files: u64,
+    f_ffree: u64,
+    f_fsid: [i32; 2],
+    f_namelen: i64,
+    f_frsize: i64,
+    f_flags: i64,
+    f_spare: [i64; 4],
+}
+
+impl KernelStatfs {
+    // 只按 ramfs 报告：总块数来自 ramfs 的内存上限，已用块数来自 ramfs 的内存占用
+    fn ramfs() -> Self {
+        let total = axfs_ramfs::ramfs_memory_limit() / PAGE_SIZE_4K;
+        let used = axfs_ramfs::ramfs_memory_usage().div_ceil(PAGE_SIZE_4K);
+        let free = total.saturating_sub(used) as u64;
+        Self {
+            f_type: RAMFS_MAGIC,
+            f_bsize: PAGE_SIZE_4K as _,
+            f_blocks: total as _,
+            f_bfree: free,
+            f_bavail: free,
+            f_namelen: NAME_MAX,
+            f_frsize: PAGE_SIZE_4K as _,
+            ..Default::default()
+        }
+    }
+}
+
+/// An entry of the `fds` array of `ppoll`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PollFd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+
+/// Terminal window size returned by `TIOCGWINSZ`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct WinSize {
+    ws_row: u16,
+    ws_col: u16,
+    ws_xpixel: u16,
+    ws_ypixel: u16,
+}
+
+impl Default for WinSize {
+    fn default() -> Self {
+        Self {
+            ws_row: 24,
+            ws_col: 80,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+}
+
+// 缺页处理：按需分配和写时复制（MAP_PRIVATE）。
+// 内核态的缺页来自系统调用访问用户内存，同样交给用户地址空间处理
+#[register_trap_handler(PAGE_FAULT)]
+fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, is_user: bool) -> bool {
+    let curr = current();
+    if curr.task_ext().aspace.lock().handle_page_fault(vaddr, access_flags) {
+        return true;
+    }
+    if is_user {
+        ax_println!("{}: segmentation fault at {:#x}, exit!", curr.id_name(), vaddr);
+        axtask::exit(-1);
+    }
+    false
+}
+
 #[register_trap_handler(SYSCALL)]
 fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize {
     ax_println!("handle_syscall [{}] ...", syscall_num);
     let ret = match syscall_num {
-         SYS_IOCTL => sys_ioctl(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _) as _,
+        SYS_GETCWD => sys_getcwd(tf.arg0() as _, tf.arg1() as _),
+        SYS_DUP => sys_dup(tf.arg0() as _),
+        SYS_DUP3 => sys_dup3(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_FCNTL => sys_fcntl(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_IOCTL => sys_ioctl(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_MKDIRAT => sys_mkdirat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_UNLINKAT => sys_unlinkat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_SYMLINKAT => sys_symlinkat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_RENAMEAT => sys_renameat2(
+            tf.arg0() as _,
+            tf.arg1() as _,
+            tf.arg2() as _,
+            tf.arg3() as _,
+            0,
+        ),
+        SYS_STATFS => sys_statfs(tf.arg0() as _, tf.arg1() as _),
+        SYS_FSTATFS => sys_fstatfs(tf.arg0() as _, tf.arg1() as _),
+        SYS_FTRUNCATE => sys_ftruncate(tf.arg0() as _, tf.arg1() as _),
         SYS_SET_TID_ADDRESS => sys_set_tid_address(tf.arg0() as _),
+        SYS_SET_ROBUST_LIST => sys_set_robust_list(tf.arg0() as _, tf.arg1() as _),
+        SYS_FACCESSAT => sys_faccessat(
+            tf.arg0() as _,
+            tf.arg1() as _,
+            tf.arg2() as _,
+            tf.arg3() as _,
+        ),
+        SYS_CHDIR => sys_chdir(tf.arg0() as _),
+        SYS_FCHDIR => sys_fchdir(tf.arg0() as _),
         SYS_OPENAT => sys_openat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
         SYS_CLOSE => sys_close(tf.arg0() as _),
+        SYS_PIPE2 => sys_pipe2(tf.arg0() as _, tf.arg1() as _),
+        SYS_GETDENTS64 => sys_getdents64(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_LSEEK => sys_lseek(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
         SYS_READ => sys_read(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
         SYS_WRITE => sys_write(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_READV => sys_readv(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
         SYS_WRITEV => sys_writev(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_PREAD64 => sys_pread64(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+        SYS_PWRITE64 => sys_pwrite64(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+        SYS_SENDFILE => sys_sendfile(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+        SYS_PPOLL => sys_ppoll(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+        SYS_READLINKAT => sys_readlinkat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+        SYS_FSTAT => sys_fstat(tf.arg0() as _, tf.arg1() as _),
         SYS_EXIT_GROUP => {
             ax_println!("[SYS_EXIT_GROUP]: system is exiting ..");
-            axtask::exit(tf.arg0() as _)
+            sys_exit(tf.arg0() as _)
         },
         SYS_EXIT => {
             ax_println!("[SYS_EXIT]: system is exiting ..");
-            axtask::exit(tf.arg0() as _)
+            sys_exit(tf.arg0() as _)
         },
+        SYS_CLONE => sys_clone(tf, tf.arg0() as _, tf.arg1() as _),
         SYS_MMAP => sys_mmap(
             tf.arg0() as _,
             tf.arg1() as _,
@@ -136,6 +406,34 @@ fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize {
             tf.arg4() as _,
             tf.arg5() as _,
         ),
+        SYS_NANOSLEEP => sys_nanosleep(tf.arg0() as _, tf.arg1() as _),
+        SYS_CLOCK_GETTIME => sys_clock_gettime(tf.arg0() as _, tf.arg1() as _),
+        SYS_RT_SIGPROCMASK => sys_rt_sigprocmask(
+            tf.arg0() as _,
+            tf.arg1() as _,
+            tf.arg2() as _,
+            tf.arg3() as _,
+        ),
+        SYS_RT_SIGPENDING => sys_rt_sigpending(tf.arg0() as _, tf.arg1() as _),
+        SYS_GETPID => sys_getpid(),
+        SYS_GETPPID => sys_getppid(),
+        SYS_GETTID => sys_gettid(),
+        SYS_SHMGET => sys_shmget(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_SHMCTL => sys_shmctl(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_SHMAT => sys_shmat(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_SHMDT => sys_shmdt(tf.arg0() as _),
+        SYS_BRK => sys_brk(tf.arg0() as _),
+        SYS_MUNMAP => sys_munmap(tf.arg0() as _, tf.arg1() as _),
+        SYS_MPROTECT => sys_mprotect(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_MSYNC => sys_msync(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _),
+        SYS_WAIT4 => sys_wait4(tf.arg0() as _, tf.arg1() as _, tf.arg2() as _, tf.arg3() as _),
+        SYS_RENAMEAT2 => sys_renameat2(
+            tf.arg0() as _,
+            tf.arg1() as _,
+            tf.arg2() as _,
+            tf.arg3() as _,
+            tf.arg4() as _,
+        ),
         _ => {
             ax_println!("Unimplemented syscall: {}", syscall_num);
             -LinuxError::ENOSYS.code() as _
@@ -144,10 +442,6 @@ fn handle_syscall(tf: &TrapFrame, syscall_num: usize) -> isize {
     ret
 }
 
-fn load_file(file: &Arc<dyn FileLike>, buf: &mut [u8], _offset: isize) -> Result<usize, i32> {
-    file.read(buf).map_err(|_| -1)
-}
-
 fn sys_mmap(
     addr: *mut usize,
     length: usize,
@@ -156,84 +450,1063 @@ fn sys_mmap(
     fd: i32,
     offset: isize,
 ) -> isize {
-    // 1. 计算映射的虚拟地址
-    let vaddr = if addr.is_null() || addr as usize == 0 {
-        alloc_user_vaddr(length)
+    syscall_body!(sys_mmap, {
+        if length == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+
+        if offset < 0 || !is_aligned_4k(offset as usize) {
+            return Err(LinuxError::EINVAL);
+        }
+
+        // 1. 匿名映射忽略 fd；否则通过 fd 获取文件对象，只支持普通文件
+        let flags = MmapFlags::from_bits_truncate(flags);
+        let anonymous = flags.contains(MmapFlags::MAP_ANONYMOUS);
+        let file = if anonymous {
+            None
+        } else {
+            let file = get_file_like(fd)?.into_any().downcast::<api::imp::fs::File>();
+            Some(file.map_err(|_| LinuxError::ENODEV)?)
+        };
+
+        // 2. 计算映射的虚拟地址，映射长度向上取整到页大小
+        let map_size = length
+            .checked_next_multiple_of(PAGE_SIZE_4K)
+            .ok_or(LinuxError::ENOMEM)?;
+        let curr = current();
+        let mut uspace = curr.task_ext().aspace.lock();
+        let vaddr = if flags.contains(MmapFlags::MAP_FIXED) {
+            if addr.is_null() || !is_aligned_4k(addr as usize) {
+                return Err(LinuxError::EINVAL);
+            }
+            addr as usize
+        } else {
+            // 非 MAP_FIXED 时 addr 只是一个提示
+            let hint = if addr.is_null() {
+                MMAP_BASE
+            } else {
+                align_up_4k(addr as usize)
+            };
+            alloc_user_vaddr(&uspace, hint, map_size).ok_or(LinuxError::ENOMEM)?
+        };
+        if vaddr.checked_add(map_size).is_none() || !uspace.contains_range(vaddr.into(), map_size) {
+            return Err(LinuxError::ENOMEM);
+        }
+        // MAP_FIXED 要求替换掉该区间内已有的映射
+        if flags.contains(MmapFlags::MAP_FIXED) {
+            unmap_user(&mut uspace, curr.task_ext(), vaddr, map_size)?;
+        }
+
+        // PROT_NONE 只占住地址区间（例如栈和堆两侧的保护区）：不分配物理页，
+        // 也不读入文件，之后任何访问都会缺页失败
+        let prot = MmapProt::from_bits_truncate(prot);
+        if prot.is_empty() {
+            uspace
+                .map_alloc(vaddr.into(), map_size, MappingFlags::empty(), false)
+                .map_err(|_| LinuxError::ENOMEM)?;
+            return Ok(vaddr);
+        }
+
+        let map_flags = MappingFlags::from(prot) | MappingFlags::USER;
+        let Some(file) = file else {
+            // 匿名映射：共享的映射到新的共享物理页（fork 出的子任务与父任务共用），
+            // 私有的按需分配清零的页
+            if flags.contains(MmapFlags::MAP_SHARED) {
+                let frames =
+                    SharedFrames::new(map_size / PAGE_SIZE_4K).map_err(|_| LinuxError::ENOMEM)?;
+                uspace
+                    .map_shared(vaddr.into(), frames, map_flags)
+                    .map_err(|_| LinuxError::ENOMEM)?;
+            } else {
+                uspace
+                    .map_alloc(vaddr.into(), map_size, map_flags, false)
+                    .map_err(|_| LinuxError::ENOMEM)?;
+            }
+            return Ok(vaddr);
+        };
+
+        // 3. 建立映射并读入文件内容，失败时撤销已建立的部分，避免泄漏物理页
+        let offset = offset as usize;
+        let shared = flags.contains(MmapFlags::MAP_SHARED);
+        if let Err(e) = map_file_pages(&mut uspace, vaddr, length, map_flags, &file, offset, shared) {
+            uspace.unmap(vaddr.into(), map_size).ok();
+            return Err(e);
+        }
+        // 4. 私有映射先设为只读，第一次写入时复制出自己的页；
+        //    共享映射记录下来，msync/munmap 时写回文件
+        if shared {
+            curr.task_ext().shared_mappings.lock().push(SharedMapping {
+                start: vaddr,
+                len: length,
+                file,
+                offset,
+            });
+        } else if map_flags.contains(MappingFlags::WRITE) {
+            uspace.mark_copy_on_write(vaddr.into(), map_size)?;
+        }
+        Ok(vaddr)
+    })
+}
+
+// 分页映射，并把文件从 offset 开始的内容逐页直接读入映射好的物理页。
+// 按位置读取，不移动 fd 的读写位置。共享映射用共享物理页，fork 出的子任务
+// 与父任务写的是同一份数据，写回文件时不会互相覆盖。
+// 新分配的页已经清零，因此最后不足一页的部分和文件末尾之后的部分都是 0
+fn map_file_pages(
+    uspace: &mut AddrSpace,
+    vaddr: usize,
+    length: usize,
+    flags: MappingFlags,
+    file: &api::imp::fs::File,
+    offset: usize,
+    shared: bool,
+) -> LinuxResult {
+    let map_size = length.next_multiple_of(PAGE_SIZE_4K);
+    if shared {
+        let frames =
+            SharedFrames::new(map_size / PAGE_SIZE_4K).map_err(|_| LinuxError::ENOMEM)?;
+        uspace.map_shared(vaddr.into(), frames, flags)
     } else {
-        addr as usize
-    };
+        uspace.map_alloc(vaddr.into(), map_size, flags, true)
+    }
+    .map_err(|_| LinuxError::ENOMEM)?;
+    let mut eof = false;
+    for page_vaddr in (vaddr..vaddr + map_size).step_by(PAGE_SIZE_4K) {
+        // 物理页不足时 populate 也可能留下未映射的页
+        let (paddr, _, _) = uspace
+            .page_table()
+            .query(page_vaddr.into())
+            .map_err(|_| LinuxError::ENOMEM)?;
+        if eof {
+            continue;
+        }
+        let len = PAGE_SIZE_4K.min(vaddr + length - page_vaddr);
+        let dst = unsafe { core::slice::from_raw_parts_mut(phys_to_virt(paddr).as_mut_ptr(), len) };
+        let file_offset = offset + (page_vaddr - vaddr);
+        eof = file.read_at(file_offset as u64, dst)? < len;
+    }
+    Ok(())
+}
 
-    // 2. 通过 fd 获取文件对象
-    let file_like = match arceos_posix_api::imp::fd_ops::get_file_like(fd) {
-        Ok(f) => f,
-        Err(_) => return -1,
-    };
-    // 3. 读取文件内容到 buf
-    let mut buf = vec![0u8; length];
-    if load_file(&file_like, &mut buf, offset).is_err() {
-        return -1;
+fn sys_munmap(addr: usize, length: usize) -> isize {
+    syscall_body!(sys_munmap, {
+        if length == 0 || !is_aligned_4k(addr) {
+            return Err(LinuxError::EINVAL);
+        }
+        let size = length.checked_next_multiple_of(PAGE_SIZE_4K).ok_or(LinuxError::EINVAL)?;
+        let curr = current();
+        let mut uspace = curr.task_ext().aspace.lock();
+        unmap_user(&mut uspace, curr.task_ext(), addr, size)?;
+        Ok(0)
+    })
+}
+
+// 把共享映射 m 在 [start, end) 内的内容写回文件，文件末尾之后的部分不写
+fn sync_shared_mapping(uspace: &AddrSpace, m: &SharedMapping, start: usize, end: usize) -> LinuxResult {
+    let file_size = m.file.stat()?.st_size as usize;
+    let end = end
+        .min(m.start + m.len)
+        .min(m.start + file_size.saturating_sub(m.offset));
+    let mut addr = start.max(m.start);
+    while addr < end {
+        let len = (PAGE_SIZE_4K - addr % PAGE_SIZE_4K).min(end - addr);
+        if let Ok((paddr, _, _)) = uspace.page_table().query(addr.into()) {
+            let src = unsafe { core::slice::from_raw_parts(phys_to_virt(paddr).as_ptr(), len) };
+            m.file.write_at((m.offset + addr - m.start) as u64, src)?;
+        }
+        addr += len;
     }
+    Ok(())
+}
+
+// 解除 [start, start + size) 的映射。其中的共享映射先写回文件，
+// 再从表中去掉（部分重叠的只保留区间外的部分）
+fn unmap_user(uspace: &mut AddrSpace, ext: &TaskExt, start: usize, size: usize) -> LinuxResult {
+    let end = start + size;
+    let mut mappings = ext.shared_mappings.lock();
+    let mut kept = Vec::new();
+    for m in mappings.drain(..) {
+        let m_end = m.start + m.len;
+        if m_end <= start || m.start >= end {
+            kept.push(m);
+            continue;
+        }
+        if let Err(e) = sync_shared_mapping(uspace, &m, start, end) {
+            warn!("failed to write back shared mapping at {:#x}: {:?}", m.start, e);
+        }
+        if m.start < start {
+            kept.push(SharedMapping {
+                start: m.start,
+                len: start - m.start,
+                file: m.file.clone(),
+                offset: m.offset,
+            });
+        }
+        if m_end > end {
+            kept.push(SharedMapping {
+                start: end,
+                len: m_end - end,
+                file: m.file.clone(),
+                offset: m.offset + (end - m.start),
+            });
+        }
+    }
+    *mappings = kept;
+    drop(mappings);
+    // 被解除映射碰到的共享内存段不能再用 shmdt 卸下
+    ext.shm_attachments
+        .lock()
+        .retain(|&(addr, size)| addr + size <= start || addr >= end);
+    uspace.unmap(start.into(), size)?;
+    Ok(())
+}
+
+// 整个区间都必须被映射区域覆盖。按区域检查而不是查页表：
+// 按需分配的页和 PROT_NONE 的保护区都还没有页表项
+fn is_mapped(uspace: &AddrSpace, addr: usize, size: usize) -> bool {
+    let range = VirtAddrRange::new(addr.into(), (addr + size).into());
+    uspace.check_region_access(range, MappingFlags::empty())
+}
 
-    // 4. 分页映射并拷贝数据
-    let page_count = (length + PAGE_SIZE_4K - 1) / PAGE_SIZE_4K;
+fn sys_msync(addr: usize, length: usize, flags: i32) -> isize {
+    syscall_body!(sys_msync, {
+        if !is_aligned_4k(addr)
+            || flags & !(MS_ASYNC | MS_INVALIDATE | MS_SYNC) != 0
+            || (flags & MS_ASYNC != 0 && flags & MS_SYNC != 0)
+        {
+            return Err(LinuxError::EINVAL);
+        }
+        let size = length.checked_next_multiple_of(PAGE_SIZE_4K).ok_or(LinuxError::ENOMEM)?;
+        let curr = current();
+        let uspace = curr.task_ext().aspace.lock();
+        if addr.checked_add(size).is_none() || !uspace.contains_range(addr.into(), size) {
+            return Err(LinuxError::ENOMEM);
+        }
+        if !is_mapped(&uspace, addr, size) {
+            return Err(LinuxError::ENOMEM);
+        }
+        // 没有页缓存，MS_ASYNC 也直接同步写回
+        for m in curr.task_ext().shared_mappings.lock().iter() {
+            sync_shared_mapping(&uspace, m, addr, addr + size)?;
+        }
+        Ok(0)
+    })
+}
+
+fn sys_brk(addr: usize) -> isize {
     let curr = current();
-    let mut uspace = curr.task_ext().aspace.lock();
-    for i in 0..page_count {
-        let page_vaddr = vaddr + i * PAGE_SIZE_4K;
+    let ext = curr.task_ext();
+    let old_top = ext.heap_top();
+    // 失败时按 Linux 语义返回原来的 break
+    if addr < crate::USER_HEAP_BASE || addr > crate::USER_HEAP_BASE + crate::USER_HEAP_SIZE_MAX {
+        return old_top as isize;
+    }
+    let old_end = align_up_4k(old_top);
+    let new_end = align_up_4k(addr);
+    let mut uspace = ext.aspace.lock();
+    let res = if new_end > old_end {
         uspace.map_alloc(
-            page_vaddr.into(),
-            PAGE_SIZE_4K,
-            MappingFlags::from(MmapProt::from_bits_truncate(prot)) | MappingFlags::USER,
-            true
-        ).unwrap();
-        let (paddr, _, _) = uspace.page_table().query(page_vaddr.into()).unwrap();
-        let start = i * PAGE_SIZE_4K;
-        let end = ((i + 1) * PAGE_SIZE_4K).min(length);
-        unsafe {
-            core::ptr::copy_nonoverlapping(
-                buf[start..end].as_ptr(),
-                phys_to_virt(paddr).as_mut_ptr(),
-                end - start,
-            );
+            old_end.into(),
+            new_end - old_end,
+            MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER,
+            true,
+        )
+    } else if new_end < old_end {
+        uspace.unmap(new_end.into(), old_end - new_end)
+    } else {
+        Ok(())
+    };
+    if res.is_err() {
+        return old_top as isize;
+    }
+    ext.set_heap_top(addr);
+    addr as isize
+}
+
+fn sys_mprotect(addr: usize, length: usize, prot: i32) -> isize {
+    syscall_body!(sys_mprotect, {
+        if !is_aligned_4k(addr) {
+            return Err(LinuxError::EINVAL);
+        }
+        let prot = MmapProt::from_bits(prot).ok_or(LinuxError::EINVAL)?;
+        let size = length.checked_next_multiple_of(PAGE_SIZE_4K).ok_or(LinuxError::ENOMEM)?;
+        if size == 0 {
+            return Ok(0);
+        }
+        let curr = current();
+        let mut uspace = curr.task_ext().aspace.lock();
+        if addr.checked_add(size).is_none() || !uspace.contains_range(addr.into(), size) {
+            return Err(LinuxError::ENOMEM);
+        }
+        if !is_mapped(&uspace, addr, size) {
+            return Err(LinuxError::ENOMEM);
+        }
+        uspace.protect(addr.into(), size, prot.into())?;
+        Ok(0)
+    })
+}
+
+fn sys_shmget(key: i32, size: usize, flags: i32) -> isize {
+    syscall_body!(sys_shmget, shm::get(key, size, flags))
+}
+
+// 只支持 IPC_RMID，已经映射的地址空间不受影响
+fn sys_shmctl(shmid: usize, cmd: i32, _buf: *mut c_void) -> isize {
+    syscall_body!(sys_shmctl, {
+        if cmd & !IPC_64 != IPC_RMID {
+            return Err(LinuxError::EINVAL);
+        }
+        shm::remove(shmid)?;
+        Ok(0)
+    })
+}
+
+// addr 为空时和 mmap 一样找一段空闲区域；不支持 SHM_RND，addr 必须按页对齐
+fn sys_shmat(shmid: usize, addr: usize, flags: i32) -> isize {
+    syscall_body!(sys_shmat, {
+        if !is_aligned_4k(addr) {
+            return Err(LinuxError::EINVAL);
+        }
+        let size = shm::size(shmid)?;
+        let curr = current();
+        let mut uspace = curr.task_ext().aspace.lock();
+        let vaddr = if addr == 0 {
+            alloc_user_vaddr(&uspace, MMAP_BASE, size).ok_or(LinuxError::ENOMEM)?
+        } else {
+            addr
+        };
+        let map_flags = if flags & SHM_RDONLY != 0 {
+            MappingFlags::READ | MappingFlags::USER
+        } else {
+            MappingFlags::READ | MappingFlags::WRITE | MappingFlags::USER
+        };
+        let size = shm::attach(shmid, &mut uspace, vaddr, map_flags)?;
+        curr.task_ext().shm_attachments.lock().push((vaddr, size));
+        Ok(vaddr)
+    })
+}
+
+fn sys_shmdt(addr: usize) -> isize {
+    syscall_body!(sys_shmdt, {
+        let curr = current();
+        let mut uspace = curr.task_ext().aspace.lock();
+        let size = curr
+            .task_ext()
+            .shm_attachments
+            .lock()
+            .iter()
+            .find(|&&(start, _)| start == addr)
+            .map(|&(_, size)| size)
+            .ok_or(LinuxError::EINVAL)?;
+        unmap_user(&mut uspace, curr.task_ext(), addr, size)?;
+        Ok(0)
+    })
+}
+
+fn sys_getcwd(buf: *mut c_char, size: usize) -> isize {
+    if buf.is_null() {
+        return -LinuxError::EFAULT.code() as _;
+    }
+    if let Err(e) = check_user_range(buf as usize, size, MappingFlags::WRITE) {
+        return -e.code() as _;
+    }
+    syscall_body!(sys_getcwd, {
+        let cwd = current().task_ext().cwd.lock().clone();
+        // 返回值和 Linux 一样是包括 '\0' 在内的长度
+        if cwd.len() + 1 > size {
+            return Err(LinuxError::ERANGE);
+        }
+        let dst = user_slice_mut(buf as *mut u8, cwd.len() + 1)?;
+        dst[..cwd.len()].copy_from_slice(cwd.as_bytes());
+        dst[cwd.len()] = 0;
+        Ok(cwd.len() + 1)
+    })
+}
+
+// 目前只支持绝对路径或相对于当前工作目录的路径
+// 相对路径按当前任务的工作目录展开，返回规范化的绝对路径（保留末尾的 '/'）
+fn resolve_path(dfd: c_int, path: *const c_char) -> LinuxResult<String> {
+    resolve_path_at(dfd, path, false)
+}
+
+// 同 resolve_path，但最后一个分量是符号链接时也会展开
+fn resolve_path_follow(dfd: c_int, path: *const c_char) -> LinuxResult<String> {
+    resolve_path_at(dfd, path, true)
+}
+
+fn resolve_path_at(dfd: c_int, path: *const c_char, follow: bool) -> LinuxResult<String> {
+    let path = user_cstr(path)?;
+    if dfd != AT_FDCWD && !path.starts_with('/') {
+        return Err(LinuxError::EBADF);
+    }
+    if path.is_empty() {
+        return Err(LinuxError::ENOENT);
+    }
+    let abs = if path.starts_with('/') {
+        canonical_path(path)
+    } else {
+        let cwd = current().task_ext().cwd.lock().clone();
+        canonical_path(&format!("{cwd}/{path}"))
+    };
+    walk_links(abs, follow)
+}
+
+// 规范化路径，保留结尾的 '/'
+fn canonical_path(path: &str) -> String {
+    let mut abs = axfs_vfs::path::canonicalize(path);
+    if path.ends_with('/') && !abs.ends_with('/') {
+        abs.push('/');
+    }
+    abs
+}
+
+// 从根开始逐个分量展开符号链接，展开后从头再走一遍。
+// 结尾带 '/' 的分量总会被展开，最后一个分量只有 follow 时才展开。
+// 不存在的分量留给后续的文件操作去报错
+fn walk_links(mut path: String, follow: bool) -> LinuxResult<String> {
+    let mut links = 0;
+    let mut end = 0;
+    while end < path.len() {
+        end = path[end + 1..].find('/').map_or(path.len(), |i| end + 1 + i);
+        if end == path.len() && !follow {
+            break;
+        }
+        let prefix = &path[..end];
+        if prefix.ends_with('/') {
+            continue;
+        }
+        let Ok(target) = axstd::fs::read_link(prefix) else {
+            continue;
+        };
+        links += 1;
+        if links > SYMLOOP_MAX {
+            return Err(LinuxError::ELOOP);
+        }
+        let rest = &path[end..];
+        path = if target.starts_with('/') {
+            canonical_path(&format!("{target}{rest}"))
+        } else {
+            let parent = &prefix[..prefix.rfind('/').unwrap()];
+            canonical_path(&format!("{parent}/{target}{rest}"))
+        };
+        end = 0;
+    }
+    Ok(path)
+}
+
+// 切换当前任务的工作目录，目标必须是已存在的目录
+fn set_cwd(path: &str) -> LinuxResult {
+    if !axstd::fs::metadata(path)?.is_dir() {
+        return Err(LinuxError::ENOTDIR);
+    }
+    let path = path.trim_end_matches('/');
+    *current().task_ext().cwd.lock() = if path.is_empty() { "/".into() } else { path.into() };
+    Ok(())
+}
+
+fn sys_chdir(path: *const c_char) -> isize {
+    syscall_body!(sys_chdir, {
+        set_cwd(&resolve_path_follow(AT_FDCWD, path)?)?;
+        Ok(0)
+    })
+}
+
+fn sys_fchdir(fd: c_int) -> isize {
+    syscall_body!(sys_fchdir, {
+        // 只有打开的文件或目录记录了路径，管道等一律不是目录
+        let file = get_file_like(fd)?
+            .into_any()
+            .downcast::<api::imp::fs::File>()
+            .map_err(|_| LinuxError::ENOTDIR)?;
+        set_cwd(file.path())?;
+        Ok(0)
+    })
+}
+
+fn sys_mkdirat(dfd: c_int, path: *const c_char, _mode: api::ctypes::mode_t) -> isize {
+    syscall_body!(sys_mkdirat, {
+        let path = resolve_path(dfd, path)?;
+        axstd::fs::create_dir(&path)?;
+        Ok(0)
+    })
+}
+
+fn sys_unlinkat(dfd: c_int, path: *const c_char, flags: c_int) -> isize {
+    syscall_body!(sys_unlinkat, {
+        let path = resolve_path(dfd, path)?;
+        if flags & !AT_REMOVEDIR != 0 {
+            return Err(LinuxError::EINVAL);
         }
+        if flags & AT_REMOVEDIR != 0 {
+            axstd::fs::remove_dir(&path)?;
+        } else {
+            axstd::fs::remove_file(&path)?;
+        }
+        Ok(0)
+    })
+}
+
+fn sys_renameat2(
+    olddfd: c_int,
+    oldpath: *const c_char,
+    newdfd: c_int,
+    newpath: *const c_char,
+    flags: u32,
+) -> isize {
+    syscall_body!(sys_renameat2, {
+        let old_path = resolve_path(olddfd, oldpath)?;
+        let new_path = resolve_path(newdfd, newpath)?;
+        if flags & !RENAME_NOREPLACE != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        if flags & RENAME_NOREPLACE != 0 && axstd::fs::metadata(&new_path).is_ok() {
+            return Err(LinuxError::EEXIST);
+        }
+        axstd::fs::rename(&old_path, &new_path)?;
+        Ok(0)
+    })
+}
+
+fn sys_symlinkat(target: *const c_char, newdfd: c_int, linkpath: *const c_char) -> isize {
+    syscall_body!(sys_symlinkat, {
+        let target = user_cstr(target)?;
+        if target.is_empty() {
+            return Err(LinuxError::ENOENT);
+        }
+        let path = resolve_path(newdfd, linkpath)?;
+        axstd::fs::symlink(target, &path)?;
+        Ok(0)
+    })
+}
+
+// 链接目标原样返回，不补 '\0'，超出 bufsiz 的部分被截断
+fn sys_readlinkat(dfd: c_int, path: *const c_char, buf: *mut u8, bufsiz: isize) -> isize {
+    syscall_body!(sys_readlinkat, {
+        if bufsiz <= 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let path = resolve_path(dfd, path)?;
+        axstd::fs::metadata(&path)?;
+        let target = axstd::fs::read_link(&path).map_err(|_| LinuxError::EINVAL)?;
+        let len = target.len().min(bufsiz as usize);
+        user_slice_mut(buf, len)?.copy_from_slice(&target.as_bytes()[..len]);
+        Ok(len as isize)
+    })
+}
+
+fn sys_dup(fd: c_int) -> isize {
+    api::sys_dup(fd) as isize
+}
+
+fn sys_dup3(old_fd: c_int, new_fd: c_int, flags: c_int) -> isize {
+    // 没有 exec，O_CLOEXEC 只需接受即可
+    if old_fd == new_fd || flags & !(api::ctypes::O_CLOEXEC as c_int) != 0 {
+        return -LinuxError::EINVAL.code() as _;
     }
-    vaddr as isize
+    api::sys_dup2(old_fd, new_fd) as isize
+}
+
+fn sys_fcntl(fd: c_int, cmd: c_int, arg: usize) -> isize {
+    api::sys_fcntl(fd, cmd, arg) as isize
+}
+
+fn sys_pipe2(fds: *mut [c_int; 2], flags: c_int) -> isize {
+    let len = core::mem::size_of::<[c_int; 2]>();
+    if let Err(e) = check_user_range(fds as usize, len, MappingFlags::WRITE) {
+        return -e.code() as _;
+    }
+    let fds = unsafe { &mut *fds };
+    api::sys_pipe2(fds, flags) as isize
+}
+
+fn sys_statfs(path: *const c_char, buf: *mut KernelStatfs) -> isize {
+    syscall_body!(sys_statfs, {
+        let path = resolve_path_follow(AT_FDCWD, path)?;
+        axstd::fs::metadata(&path)?;
+        copy_to_user(buf, &KernelStatfs::ramfs())?;
+        Ok(0)
+    })
+}
+
+fn sys_fstatfs(fd: c_int, buf: *mut KernelStatfs) -> isize {
+    syscall_body!(sys_fstatfs, {
+        get_file_like(fd)?;
+        copy_to_user(buf, &KernelStatfs::ramfs())?;
+        Ok(0)
+    })
+}
+
+fn sys_faccessat(dfd: c_int, path: *const c_char, mode: c_int, flags: c_int) -> isize {
+    syscall_body!(sys_faccessat, {
+        if mode & !(F_OK | R_OK | W_OK | X_OK) != 0
+            || flags & !(AT_EACCESS | AT_SYMLINK_NOFOLLOW) != 0
+        {
+            return Err(LinuxError::EINVAL);
+        }
+        let path = resolve_path_at(dfd, path, flags & AT_SYMLINK_NOFOLLOW == 0)?;
+        let perm = axstd::fs::metadata(&path)?.permissions();
+        // 只有一个用户，按 owner 权限位检查
+        if (mode & R_OK != 0 && !perm.owner_readable())
+            || (mode & W_OK != 0 && !perm.owner_writable())
+            || (mode & X_OK != 0 && !perm.owner_executable())
+        {
+            return Err(LinuxError::EACCES);
+        }
+        Ok(0)
+    })
 }
 
 fn sys_openat(dfd: c_int, fname: *const c_char, flags: c_int, mode: api::ctypes::mode_t) -> isize {
-    assert_eq!(dfd, AT_FDCWD);
-    api::sys_open(fname, flags, mode) as isize
+    // 先校验并展开路径，再交给 api 打开
+    let path = match resolve_path_follow(dfd, fname) {
+        Ok(path) => path,
+        Err(e) => return -e.code() as _,
+    };
+    let path = CString::new(path).unwrap();
+    api::sys_open(path.as_ptr(), flags, mode) as isize
 }
 
 fn sys_close(fd: i32) -> isize {
     api::sys_close(fd) as isize
 }
 
+// 把目录项按 linux_dirent64 的格式填入用户缓冲区，读写位置是目录项的序号
+// 记录格式：d_ino(8) d_off(8) d_reclen(2) d_type(1) d_name('\0' 结尾)，按 8 字节对齐
+fn sys_getdents64(fd: i32, dirp: *mut u8, count: usize) -> isize {
+    const HEADER_LEN: usize = 19;
+    syscall_body!(sys_getdents64, {
+        let file = get_file_like(fd)?
+            .into_any()
+            .downcast::<api::imp::fs::File>()
+            .map_err(|_| LinuxError::ENOTDIR)?;
+        let buf = user_slice_mut(dirp, count)?;
+        let mut pos = file.seek(SeekFrom::Current(0))? as usize;
+        let mut written = 0;
+        let mut dirents = [axfs_vfs::VfsDirEntry::default(); 16];
+        'outer: loop {
+            let n = file.read_dir_at(pos, &mut dirents)?;
+            if n == 0 {
+                break;
+            }
+            for ent in &dirents[..n] {
+                let name = ent.name_as_bytes();
+                let reclen = (HEADER_LEN + name.len() + 1).next_multiple_of(8);
+                if written + reclen > buf.len() {
+                    // 一项都放不下时报错，否则留到下次调用
+                    if written == 0 {
+                        return Err(LinuxError::EINVAL);
+                    }
+                    break 'outer;
+                }
+                pos += 1;
+                let rec = &mut buf[written..written + reclen];
+                rec[0..8].copy_from_slice(&1u64.to_ne_bytes());
+                rec[8..16].copy_from_slice(&(pos as i64).to_ne_bytes());
+                rec[16..18].copy_from_slice(&(reclen as u16).to_ne_bytes());
+                rec[18] = ent.entry_type() as u8;
+                rec[HEADER_LEN..HEADER_LEN + name.len()].copy_from_slice(name);
+                rec[HEADER_LEN + name.len()..].fill(0);
+                written += reclen;
+            }
+        }
+        file.seek(SeekFrom::Start(pos as u64))?;
+        Ok(written)
+    })
+}
+
+fn sys_lseek(fd: i32, offset: i64, whence: i32) -> isize {
+    api::sys_lseek(fd, offset as _, whence) as isize
+}
+
 fn sys_read(fd: i32, buf: *mut c_void, count: usize) -> isize {
+    if let Err(e) = check_user_range(buf as usize, count, MappingFlags::WRITE) {
+        return -e.code() as _;
+    }
     api::sys_read(fd, buf, count)
 }
 
 fn sys_write(fd: i32, buf: *const c_void, count: usize) -> isize {
+    if let Err(e) = check_user_range(buf as usize, count, MappingFlags::READ) {
+        return -e.code() as _;
+    }
     api::sys_write(fd, buf, count)
 }
 
+fn sys_readv(fd: i32, iov: *const api::ctypes::iovec, iocnt: i32) -> isize {
+    syscall_body!(sys_readv, {
+        let iovs = user_iovecs(iov, iocnt, MappingFlags::WRITE)?;
+        let file_like = get_file_like(fd)?;
+        let mut total = 0;
+        for iov in iovs {
+            if iov.iov_len == 0 {
+                continue;
+            }
+            let buf = user_slice_mut(iov.iov_base as *mut u8, iov.iov_len as usize)?;
+            let n = file_like.read(buf)?;
+            total += n;
+            // 读到文件末尾就停止
+            if n < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    })
+}
+
 fn sys_writev(fd: i32, iov: *const api::ctypes::iovec, iocnt: i32) -> isize {
+    if let Err(e) = user_iovecs(iov, iocnt, MappingFlags::READ) {
+        return -e.code() as _;
+    }
     unsafe { api::sys_writev(fd, iov, iocnt) }
 }
 
+// 读入用户的 iovec 数组，并检查每个缓冲区都允许 `flags` 访问
+// iocnt 须在 [0, IOV_MAX] 内，所有长度之和不能超过 isize::MAX
+fn user_iovecs<'a>(
+    iov: *const api::ctypes::iovec,
+    iocnt: i32,
+    flags: MappingFlags,
+) -> LinuxResult<&'a [api::ctypes::iovec]> {
+    if !(0..=IOV_MAX).contains(&iocnt) {
+        return Err(LinuxError::EINVAL);
+    }
+    let len = iocnt as usize * core::mem::size_of::<api::ctypes::iovec>();
+    check_user_range(iov as usize, len, MappingFlags::READ)?;
+    if iocnt == 0 {
+        return Ok(&[]);
+    }
+    let iovs = unsafe { core::slice::from_raw_parts(iov, iocnt as usize) };
+    let mut total: usize = 0;
+    for iov in iovs {
+        total = total
+            .checked_add(iov.iov_len as usize)
+            .filter(|&total| total <= isize::MAX as usize)
+            .ok_or(LinuxError::EINVAL)?;
+    }
+    for iov in iovs {
+        check_user_range(iov.iov_base as usize, iov.iov_len as usize, flags)?;
+    }
+    Ok(iovs)
+}
+
+fn sys_pread64(fd: i32, buf: *mut c_void, count: usize, offset: i64) -> isize {
+    if let Err(e) = check_user_range(buf as usize, count, MappingFlags::WRITE) {
+        return -e.code() as _;
+    }
+    api::sys_pread64(fd, buf, count, offset)
+}
+
+fn sys_pwrite64(fd: i32, buf: *const c_void, count: usize, offset: i64) -> isize {
+    if let Err(e) = check_user_range(buf as usize, count, MappingFlags::READ) {
+        return -e.code() as _;
+    }
+    api::sys_pwrite64(fd, buf, count, offset)
+}
+
+// 在内核中把 in_fd 的内容搬到 out_fd，不经过用户空间
+// offset 非空时从 *offset 处读取并更新它，in_fd 的读写位置不变；否则从当前位置读取并前移
+fn sys_sendfile(out_fd: i32, in_fd: i32, offset: *mut i64, count: usize) -> isize {
+    syscall_body!(sys_sendfile, {
+        let out_file = get_file_like(out_fd)?;
+        let in_file = get_file_like(in_fd)?
+            .into_any()
+            .downcast::<api::imp::fs::File>()
+            .map_err(|_| LinuxError::EINVAL)?;
+        let mut pos = if offset.is_null() {
+            in_file.seek(SeekFrom::Current(0))?
+        } else {
+            u64::try_from(copy_from_user(offset)?).map_err(|_| LinuxError::EINVAL)?
+        };
+        let count = count.min(isize::MAX as usize);
+        let mut buf = alloc::vec![0; count.min(PAGE_SIZE_4K)];
+        let mut total = 0;
+        while total < count {
+            let len = buf.len().min(count - total);
+            let n = in_file.read_at(pos, &mut buf[..len])?;
+            if n == 0 {
+                break;
+            }
+            // 已经搬过一部分时，写入出错只返回已搬运的字节数
+            let written = match out_file.write(&buf[..n]) {
+                Ok(written) => written,
+                Err(_) if total > 0 => 0,
+                Err(e) => return Err(e),
+            };
+            total += written;
+            pos += written as u64;
+            if written < n {
+                break;
+            }
+        }
+        if offset.is_null() {
+            in_file.seek(SeekFrom::Start(pos))?;
+        } else {
+            copy_to_user(offset, &(pos as i64))?;
+        }
+        Ok(total)
+    })
+}
+
+fn sys_ftruncate(fd: i32, length: i64) -> isize {
+    api::sys_ftruncate(fd, length) as isize
+}
+
+// 轮询所有 fd，等到至少一个就绪或超时；没有信号，sigmask 忽略
+fn sys_ppoll(
+    fds: *mut PollFd,
+    nfds: usize,
+    timeout: *const api::ctypes::timespec,
+    _sigmask: *const c_void,
+) -> isize {
+    syscall_body!(sys_ppoll, {
+        if nfds > api::imp::fd_ops::AX_FILE_LIMIT {
+            return Err(LinuxError::EINVAL);
+        }
+        let len = nfds * core::mem::size_of::<PollFd>();
+        check_user_range(fds as usize, len, MappingFlags::READ | MappingFlags::WRITE)?;
+        let fds: &mut [PollFd] = if nfds == 0 {
+            &mut []
+        } else {
+            unsafe { core::slice::from_raw_parts_mut(fds, nfds) }
+        };
+        let deadline = if timeout.is_null() {
+            None
+        } else {
+            let ts = copy_from_user(timeout)?;
+            if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+                return Err(LinuxError::EINVAL);
+            }
+            Some(axhal::time::wall_time() + ts.into())
+        };
+        loop {
+            let mut ready = 0;
+            for pfd in fds.iter_mut() {
+                pfd.revents = poll_fd(pfd.fd, pfd.events);
+                if pfd.revents != 0 {
+                    ready += 1;
+                }
+            }
+            if ready > 0 || deadline.is_some_and(|ddl| axhal::time::wall_time() >= ddl) {
+                return Ok(ready);
+            }
+            axtask::yield_now();
+        }
+    })
+}
+
+// 返回 fd 当前就绪的事件；负的 fd 被忽略，无效的 fd 报告 POLLNVAL
+fn poll_fd(fd: i32, events: i16) -> i16 {
+    if fd < 0 {
+        return 0;
+    }
+    let state = match get_file_like(fd).and_then(|f| f.poll()) {
+        Ok(state) => state,
+        Err(_) => return POLLNVAL,
+    };
+    let mut revents = 0;
+    if state.readable {
+        revents |= POLLIN;
+    }
+    if state.writable {
+        revents |= POLLOUT;
+    }
+    revents & events
+}
+
+fn sys_fstat(fd: i32, statbuf: *mut KernelStat) -> isize {
+    syscall_body!(sys_fstat, {
+        let st = get_file_like(fd)?.stat()?;
+        copy_to_user(statbuf, &st.into())?;
+        Ok(0)
+    })
+}
+
+fn sys_clock_gettime(clk: i32, ts: *mut api::ctypes::timespec) -> isize {
+    let len = core::mem::size_of::<api::ctypes::timespec>();
+    if let Err(e) = check_user_range(ts as usize, len, MappingFlags::WRITE) {
+        return -e.code() as _;
+    }
+    unsafe { api::sys_clock_gettime(clk, ts) as isize }
+}
+
+fn sys_nanosleep(req: *const api::ctypes::timespec, rem: *mut api::ctypes::timespec) -> isize {
+    // api 对空指针返回 EINVAL，Linux 返回 EFAULT；负的秒数也要拒绝
+    let len = core::mem::size_of::<api::ctypes::timespec>();
+    let req = match copy_from_user(req) {
+        Ok(req) => req,
+        Err(e) => return -e.code() as _,
+    };
+    if req.tv_sec < 0 {
+        return -LinuxError::EINVAL.code() as _;
+    }
+    if !rem.is_null() {
+        if let Err(e) = check_user_range(rem as usize, len, MappingFlags::WRITE) {
+            return -e.code() as _;
+        }
+    }
+    unsafe { api::sys_nanosleep(&req, rem) as isize }
+}
+
+// 只支持 fork：子任务得到父任务地址空间的一份拷贝，从 ecall 的下一条指令开始
+// 运行并返回 0。文件描述符表是全局的，父子任务共用。没有信号，忽略退出信号
+fn sys_clone(tf: &TrapFrame, flags: usize, stack: usize) -> isize {
+    syscall_body!(sys_clone, {
+        if flags & !CSIGNAL != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let curr = current();
+        let mut aspace = axmm::new_user_aspace().map_err(|_| LinuxError::ENOMEM)?;
+        aspace
+            .copy_areas_from(&curr.task_ext().aspace.lock())
+            .map_err(|_| LinuxError::ENOMEM)?;
+
+        let mut uctx = UspaceContext::from(tf);
+        uctx.set_ip(uctx.get_ip() + 4);
+        uctx.set_retval(0);
+        if stack != 0 {
+            uctx.set_sp(stack);
+        }
+        let child = spawn_child_task(curr.task_ext(), Arc::new(Mutex::new(aspace)), uctx);
+        let pid = child.task_ext().proc_id;
+        curr.task_ext().children.lock().push(child);
+        Ok(pid)
+    })
+}
+
+// 先记下退出码，父任务的 wait4 据此判断子任务是否已经退出
+fn sys_exit(exit_code: i32) -> ! {
+    current().task_ext().set_exit_code(exit_code);
+    axtask::exit(exit_code)
+}
+
+// pid 为 -1 时等待任意子任务；只有一个进程组，0 和小于 -1 的 pid 也按任意子任务处理。
+// 退出状态按 W_EXITCODE(code, 0) 编码，不统计资源用量
+fn sys_wait4(pid: i32, status: *mut i32, options: i32, _rusage: *mut c_void) -> isize {
+    syscall_body!(sys_wait4, {
+        if options & !WNOHANG != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let curr = current();
+        let child = loop {
+            let mut children = curr.task_ext().children.lock();
+            let is_target =
+                |child: &AxTaskRef| pid <= 0 || child.task_ext().proc_id == pid as usize;
+            if !children.iter().any(is_target) {
+                return Err(LinuxError::ECHILD);
+            }
+            let exited = children
+                .iter()
+                .position(|child| is_target(child) && child.task_ext().exit_code().is_some());
+            if let Some(idx) = exited {
+                break children.remove(idx);
+            }
+            drop(children);
+            if options & WNOHANG != 0 {
+                return Ok(0);
+            }
+            axtask::yield_now();
+        };
+        // 退出码在 axtask::exit 之前就记下了，等它真正退出后再回收
+        let exit_code = child.join().unwrap_or(0);
+        if !status.is_null() {
+            copy_to_user(status, &((exit_code & 0xff) << 8))?;
+        }
+        Ok(child.task_ext().proc_id as isize)
+    })
+}
+
 fn sys_set_tid_address(tid_ptd: *const i32) -> isize {
     let curr = current();
     curr.task_ext().set_clear_child_tid(tid_ptd as _);
     curr.id().as_u64() as isize
 }
 
-fn sys_ioctl(_fd: i32, _op: usize, _argp: *mut c_void) -> i32 {
-    ax_println!("Ignore SYS_IOCTL");
+// 每个进程只有一个线程，pid 与 tid 相同，都是任务 ID
+// 只记录链表头，没有 futex，退出时无需处理
+fn sys_set_robust_list(head: usize, len: usize) -> isize {
+    if len != ROBUST_LIST_HEAD_SIZE {
+        return -LinuxError::EINVAL.code() as _;
+    }
+    current().task_ext().set_robust_list_head(head as _);
     0
 }
 
-// 占位：你需要实现一个用户空间虚拟地址分配器
-fn alloc_user_vaddr(_length: usize) -> usize {
-    // TODO: 实现真正的分配逻辑
-    0x8000_0000 // 示例返回一个固定地址
+// 没有信号投递，只维护阻塞集合，sigpending 等据此保持一致
+fn sys_rt_sigprocmask(how: i32, set: *const u64, oldset: *mut u64, sigsetsize: usize) -> isize {
+    syscall_body!(sys_rt_sigprocmask, {
+        if sigsetsize != core::mem::size_of::<u64>() {
+            return Err(LinuxError::EINVAL);
+        }
+        let ext = current().task_ext();
+        let old = ext.signal_mask();
+        // 先读 set 再写 oldset，两者可能指向同一处
+        if !set.is_null() {
+            let set = copy_from_user(set)?;
+            let mask = match how {
+                SIG_BLOCK => old | set,
+                SIG_UNBLOCK => old & !set,
+                SIG_SETMASK => set,
+                _ => return Err(LinuxError::EINVAL),
+            };
+            ext.set_signal_mask(mask & !UNBLOCKABLE_SIGNALS);
+        }
+        if !oldset.is_null() {
+            copy_to_user(oldset, &old)?;
+        }
+        Ok(0)
+    })
+}
+
+// 从不投递信号，也就没有待处理的信号
+fn sys_rt_sigpending(set: *mut u64, sigsetsize: usize) -> isize {
+    syscall_body!(sys_rt_sigpending, {
+        if sigsetsize > core::mem::size_of::<u64>() {
+            return Err(LinuxError::EINVAL);
+        }
+        user_slice_mut(set as *mut u8, sigsetsize)?.fill(0);
+        Ok(0)
+    })
+}
+
+fn sys_getpid() -> isize {
+    current().task_ext().proc_id as isize
+}
+
+fn sys_getppid() -> isize {
+    current().task_ext().parent_id as isize
+}
+
+fn sys_gettid() -> isize {
+    current().id().as_u64() as isize
+}
+
+fn sys_ioctl(fd: i32, op: usize, argp: *mut c_void) -> isize {
+    syscall_body!(sys_ioctl, {
+        let file_like = get_file_like(fd)?;
+        // 先按 op 分派，只有会访问 argp 的操作才检查它（copy_*_user 对空指针返回 EFAULT）
+        match op {
+            FIONBIO => {
+                let nonblocking = copy_from_user(argp as *const c_int)? != 0;
+                file_like.set_nonblocking(nonblocking)?;
+                Ok(0)
+            }
+            // 只有标准输入输出被视为终端
+            TIOCGWINSZ if (0..=2).contains(&fd) => {
+                copy_to_user(argp as *mut WinSize, &WinSize::default())?;
+                Ok(0)
+            }
+            _ => Err(LinuxError::ENOTTY),
+        }
+    })
+}
+
+// 从 hint 开始，在用户地址空间中查找一段足够大的空闲区域
+fn alloc_user_vaddr(uspace: &AddrSpace, hint: usize, length: usize) -> Option<usize> {
+    uspace
+        .find_free_area(
+            hint.into(),
+            length,
+            VirtAddrRange::new(uspace.base(), uspace.end()),
+        )
+        .map(|vaddr| vaddr.as_usize())
 }
@@ -2,36 +2,96 @@
 
 use core::sync::atomic::AtomicU64;
 
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 use axhal::arch::UspaceContext;
 use axmm::AddrSpace;
 use axsync::Mutex;
 use axtask::{AxTaskRef, TaskExtRef, TaskInner};
 
+/// A `MAP_SHARED` file mapping, whose changes are written back to the file on
+/// `msync` and `munmap`.
+#[derive(Clone)]
+pub struct SharedMapping {
+    /// The start address of the mapping.
+    pub start: usize,
+    /// The length of the mapping in bytes.
+    pub len: usize,
+    /// The mapped file.
+    pub file: Arc<arceos_posix_api::imp::fs::File>,
+    /// The file offset that `start` is mapped to.
+    pub offset: usize,
+}
+
 /// Task extended data for the monolithic kernel.
 pub struct TaskExt {
     /// The process ID.
+    ///
+    /// There is only one thread in each process, so it is the same as the
+    /// task ID (thread ID).
     pub proc_id: usize,
+    /// The parent process ID, 0 for the first user task spawned by the kernel.
+    pub parent_id: usize,
     /// The clear thread tid field
     ///
     /// See <https://manpages.debian.org/unstable/manpages-dev/set_tid_address.2.en.html#clear_child_tid>
     ///
     /// When the thread exits, the kernel clears the word at this address if it is not NULL.
     clear_child_tid: AtomicU64,
+    /// The head of the robust futex list registered by `set_robust_list`.
+    ///
+    /// Only recorded: there are no futexes to release when the task exits.
+    robust_list_head: AtomicU64,
+    /// The set of blocked signals, bit `n - 1` for signal `n`.
+    signal_mask: AtomicU64,
+    /// The current program break (end of the heap).
+    heap_top: AtomicU64,
     /// The user space context.
     pub uctx: UspaceContext,
     /// The virtual memory address space.
     pub aspace: Arc<Mutex<AddrSpace>>,
+    /// The `MAP_SHARED` file mappings in `aspace`.
+    ///
+    /// Always lock `aspace` first when both are needed.
+    pub shared_mappings: Mutex<Vec<SharedMapping>>,
+    /// The System V shared memory segments attached by `shmat`, as
+    /// `(start, size)` pairs.
+    ///
+    /// Always lock `aspace` first when both are needed.
+    pub shm_attachments: Mutex<Vec<(usize, usize)>>,
+    /// The current working directory, an absolute path in canonical form.
+    ///
+    /// Relative paths in syscalls are resolved against it.
+    pub cwd: Mutex<String>,
+    /// The child tasks that have not been reaped by `wait4` yet.
+    pub children: Mutex<Vec<AxTaskRef>>,
+    /// The exit code passed to `exit`, `None` while the task is running.
+    exit_code: Mutex<Option<i32>>,
 }
 
 impl TaskExt {
-    pub const fn new(uctx: UspaceContext, aspace: Arc<Mutex<AddrSpace>>) -> Self {
+    pub fn new(
+        proc_id: usize,
+        parent_id: usize,
+        uctx: UspaceContext,
+        aspace: Arc<Mutex<AddrSpace>>,
+    ) -> Self {
         Self {
-            proc_id: 233,
+            proc_id,
+            parent_id,
             uctx,
             clear_child_tid: AtomicU64::new(0),
+            robust_list_head: AtomicU64::new(0),
+            signal_mask: AtomicU64::new(0),
+            heap_top: AtomicU64::new(crate::USER_HEAP_BASE as u64),
             aspace,
+            shared_mappings: Mutex::new(Vec::new()),
+            shm_attachments: Mutex::new(Vec::new()),
+            cwd: Mutex::new(String::from("/")),
+            children: Mutex::new(Vec::new()),
+            exit_code: Mutex::new(None),
         }
     }
 
@@ -44,11 +104,47 @@ impl TaskExt {
         self.clear_child_tid
             .store(clear_child_tid, core::sync::atomic::Ordering::Relaxed);
     }
+
+    pub(crate) fn robust_list_head(&self) -> u64 {
+        self.robust_list_head
+            .load(core::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_robust_list_head(&self, head: u64) {
+        self.robust_list_head
+            .store(head, core::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub(crate) fn signal_mask(&self) -> u64 {
+        self.signal_mask.load(core::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_signal_mask(&self, mask: u64) {
+        self.signal_mask
+            .store(mask, core::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub(crate) fn exit_code(&self) -> Option<i32> {
+        *self.exit_code.lock()
+    }
+
+    pub(crate) fn set_exit_code(&self, exit_code: i32) {
+        *self.exit_code.lock() = Some(exit_code);
+    }
+
+    pub(crate) fn heap_top(&self) -> usize {
+        self.heap_top.load(core::sync::atomic::Ordering::Relaxed) as usize
+    }
+
+    pub(crate) fn set_heap_top(&self, heap_top: usize) {
+        self.heap_top
+            .store(heap_top as u64, core::sync::atomic::Ordering::Relaxed);
+    }
 }
 
 axtask::def_task_ext!(TaskExt);
 
-pub fn spawn_user_task(aspace: Arc<Mutex<AddrSpace>>, uctx: UspaceContext) -> AxTaskRef {
+fn new_user_task(aspace: &Mutex<AddrSpace>, name: &str) -> TaskInner {
     let mut task = TaskInner::new(
         || {
             let curr = axtask::current();
@@ -61,11 +157,39 @@ pub fn spawn_user_task(aspace: Arc<Mutex<AddrSpace>>, uctx: UspaceContext) -> Ax
             );
             unsafe { curr.task_ext().uctx.enter_uspace(kstack_top) };
         },
-        "userboot".into(),
+        name.into(),
         crate::KERNEL_STACK_SIZE,
     );
     task.ctx_mut()
         .set_page_table_root(aspace.lock().page_table_root());
-    task.init_task_ext(TaskExt::new(uctx, aspace));
+    task
+}
+
+pub fn spawn_user_task(aspace: Arc<Mutex<AddrSpace>>, uctx: UspaceContext) -> AxTaskRef {
+    let mut task = new_user_task(&aspace, "userboot");
+    let proc_id = task.id().as_u64() as usize;
+    task.init_task_ext(TaskExt::new(proc_id, 0, uctx, aspace));
+    axtask::spawn_task(task)
+}
+
+/// Spawns a child of `parent` running in `aspace` from `uctx`, as `fork` does.
+///
+/// The child inherits the working directory, the program break, the signal
+/// mask, the `MAP_SHARED` file mappings and the attached shared memory
+/// segments of the parent.
+pub fn spawn_child_task(
+    parent: &TaskExt,
+    aspace: Arc<Mutex<AddrSpace>>,
+    uctx: UspaceContext,
+) -> AxTaskRef {
+    let mut task = new_user_task(&aspace, "userfork");
+    let proc_id = task.id().as_u64() as usize;
+    let ext = TaskExt::new(proc_id, parent.proc_id, uctx, aspace);
+    ext.set_heap_top(parent.heap_top());
+    ext.set_signal_mask(parent.signal_mask());
+    *ext.cwd.lock() = parent.cwd.lock().clone();
+    *ext.shared_mappings.lock() = parent.shared_mappings.lock().clone();
+    *ext.shm_attachments.lock() = parent.shm_attachments.lock().clone();
+    task.init_task_ext(ext);
     axtask::spawn_task(task)
 }
@@ -0,0 +1,81 @@
+//! 访问用户态内存的辅助函数
+//!
+//! 系统调用收到的指针都来自用户程序，使用前必须确认它们落在当前进程
+//! 已映射、并且权限正确的区域内，否则返回 EFAULT，而不是让内核缺页崩溃。
+
+use core::ffi::{c_char, CStr};
+use core::mem::size_of;
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::mem::PAGE_SIZE_4K;
+use axhal::paging::MappingFlags;
+use axtask::{current, TaskExtRef};
+use memory_addr::{align_down_4k, VirtAddrRange};
+
+// 路径等字符串的最大长度（含结尾的 '\0'）
+const MAX_CSTR_LEN: usize = 4096;
+
+/// 检查 `[addr, addr + len)` 是否都在当前进程允许 `flags` 访问的用户区域内
+pub fn check_user_range(addr: usize, len: usize, flags: MappingFlags) -> LinuxResult {
+    if len == 0 {
+        return Ok(());
+    }
+    let end = addr.checked_add(len).ok_or(LinuxError::EFAULT)?;
+    // 只在检查时持有锁，真正访问时可能缺页，缺页处理还要再拿这把锁
+    let aspace = current().task_ext().aspace.lock();
+    let range = VirtAddrRange::new(addr.into(), end.into());
+    if addr == 0 || !aspace.check_region_access(range, flags | MappingFlags::USER) {
+        return Err(LinuxError::EFAULT);
+    }
+    Ok(())
+}
+
+/// 从用户地址 `src` 读出一个 `T`
+pub fn copy_from_user<T: Copy>(src: *const T) -> LinuxResult<T> {
+    check_user_range(src as usize, size_of::<T>(), MappingFlags::READ)?;
+    Ok(unsafe { src.read_unaligned() })
+}
+
+/// 把 `val` 写到用户地址 `dst`
+pub fn copy_to_user<T: Copy>(dst: *mut T, val: &T) -> LinuxResult {
+    check_user_range(dst as usize, size_of::<T>(), MappingFlags::WRITE)?;
+    unsafe { dst.write_unaligned(*val) };
+    Ok(())
+}
+
+/// 把用户地址 `ptr` 开始的 `len` 个字节作为可写切片
+pub fn user_slice_mut<'a>(ptr: *mut u8, len: usize) -> LinuxResult<&'a mut [u8]> {
+    check_user_range(ptr as usize, len, MappingFlags::WRITE)?;
+    if len == 0 {
+        return Ok(&mut []);
+    }
+    Ok(unsafe { core::slice::from_raw_parts_mut(ptr, len) })
+}
+
+/// 读取用户地址 `ptr` 处以 '\0' 结尾的 UTF-8 字符串
+///
+/// 逐页检查后再扫描，不会越过未映射的页。
+pub fn user_cstr<'a>(ptr: *const c_char) -> LinuxResult<&'a str> {
+    let start = ptr as usize;
+    let mut page = align_down_4k(start);
+    loop {
+        let scan_start = start.max(page);
+        let scan_end = page + PAGE_SIZE_4K;
+        check_user_range(scan_start, scan_end - scan_start, MappingFlags::READ)?;
+        let bytes = unsafe {
+            core::slice::from_raw_parts(scan_start as *const u8, scan_end - scan_start)
+        };
+        if bytes.contains(&0) {
+            break;
+        }
+        if scan_end - start >= MAX_CSTR_LEN {
+            return Err(LinuxError::ENAMETOOLONG);
+        }
+        page = scan_end;
+    }
+    let cstr = unsafe { CStr::from_ptr(ptr) };
+    if cstr.to_bytes().len() >= MAX_CSTR_LEN {
+        return Err(LinuxError::ENAMETOOLONG);
+    }
+    cstr.to_str().map_err(|_| LinuxError::EINVAL)
+}
@@ -87,3 +87,16 @@ pub fn remove_file(path: &str) -> io::Result<()> {
 pub fn rename(old: &str, new: &str) -> io::Result<()> {
     crate::root::rename(old, new)
 }
+
+/// Creates a symbolic link at `path` which points to `target`.
+///
+/// The target is stored as-is and is not required to exist. Only directories
+/// on a ramfs support symbolic links for now.
+pub fn symlink(target: &str, path: &str) -> io::Result<()> {
+    crate::root::symlink(target, path)
+}
+
+/// Reads the target of the symbolic link at `path`.
+pub fn read_link(path: &str) -> io::Result<String> {
+    crate::root::read_link(path)
+}
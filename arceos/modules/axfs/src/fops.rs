@@ -156,11 +156,30 @@ impl File {
         if opts.truncate {
             node.truncate(0)?;
         }
-        Ok(Self {
+        let file = Self {
             node: WithCap::new(node, access_cap),
             is_append: opts.append,
             offset: 0,
-        })
+        };
+        #[cfg(feature = "ramfs")]
+        file.track_fifo_end(true);
+        Ok(file)
+    }
+
+    /// Records the opened or closed end of a FIFO, which reads at end of file
+    /// without writers and fails to write without readers.
+    #[cfg(feature = "ramfs")]
+    fn track_fifo_end(&self, open: bool) {
+        let node = unsafe { self.node.access_unchecked() };
+        if let Some(fifo) = node.as_any().downcast_ref::<axfs_ramfs::FifoNode>() {
+            let read = self.access_node(Cap::READ).is_ok();
+            let write = self.access_node(Cap::WRITE).is_ok();
+            if open {
+                fifo.open_end(read, write);
+            } else {
+                fifo.close_end(read, write);
+            }
+        }
     }
 
     /// Opens a file at the path relative to the current directory. Returns a
@@ -169,6 +188,20 @@ impl File {
         Self::_open_at(None, path, opts)
     }
 
+    /// Reads directory entries starting from the `start_idx`-th one into
+    /// `dirents`, if the file is a directory. Returns the number of entries
+    /// read, which is 0 at the end of the directory.
+    ///
+    /// The entries include `.` and `..`. The cursor is not changed.
+    pub fn read_dir_at(&self, start_idx: usize, dirents: &mut [DirEntry]) -> AxResult<usize> {
+        self.access_node(Cap::READ)?.read_dir(start_idx, dirents)
+    }
+
+    /// Sets whether writes go to the end of the file, like `O_APPEND`.
+    pub fn set_append(&mut self, append: bool) {
+        self.is_append = append;
+    }
+
     /// Truncates the file to the specified size.
     pub fn truncate(&self, size: u64) -> AxResult {
         self.access_node(Cap::WRITE)?.truncate(size)?;
@@ -201,6 +234,19 @@ impl File {
     /// After the write, the cursor will be advanced by the number of bytes
     /// written.
     pub fn write(&mut self, buf: &[u8]) -> AxResult<usize> {
+        #[cfg(feature = "ramfs")]
+        if self.is_append {
+            // ramfs files can append atomically, so that appends from
+            // different handles never overwrite each other.
+            let node = self.access_node(Cap::WRITE)?;
+            if node.get_attr()?.is_file() {
+                if let Some(file) = node.as_any().downcast_ref::<crate::fs::ramfs::FileNode>() {
+                    let (offset, write_len) = file.append(buf)?;
+                    self.offset = offset + write_len as u64;
+                    return Ok(write_len);
+                }
+            }
+        }
         let offset = if self.is_append {
             self.get_attr()?.size()
         } else {
@@ -349,6 +395,8 @@ impl Directory {
 
 impl Drop for File {
     fn drop(&mut self) {
+        #[cfg(feature = "ramfs")]
+        self.track_fifo_end(false);
         unsafe { self.node.access_unchecked().release().ok() };
     }
 }
@@ -16,8 +16,14 @@ pub(crate) fn devfs() -> Arc<fs::devfs::DeviceFileSystem> {
     Arc::new(devfs)
 }
 
+/// The maximum memory used by all RAM filesystems, so that filling `/tmp`
+/// fails with `ENOSPC` instead of exhausting the kernel heap.
+#[cfg(feature = "ramfs")]
+const RAMFS_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
 #[cfg(feature = "ramfs")]
 pub(crate) fn ramfs() -> Arc<fs::ramfs::RamFileSystem> {
+    fs::ramfs::set_ramfs_memory_limit(RAMFS_MEMORY_LIMIT);
     Arc::new(fs::ramfs::RamFileSystem::new())
 }
 
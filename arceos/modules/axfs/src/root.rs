@@ -311,3 +311,28 @@ pub(crate) fn rename(old: &str, new: &str) -> AxResult {
     }
     parent_node_of(None, old).rename(old, new)
 }
+
+pub(crate) fn symlink(target: &str, path: &str) -> AxResult {
+    let abs_path = absolute_path(path)?;
+    let (parent, name) = abs_path.rsplit_once('/').unwrap();
+    if name.is_empty() || path.ends_with('/') {
+        return ax_err!(InvalidInput);
+    }
+    let parent = lookup(None, &(String::from(parent) + "/"))?;
+    #[cfg(feature = "ramfs")]
+    if let Some(dir) = parent.as_any().downcast_ref::<fs::ramfs::DirNode>() {
+        return dir.create_symlink(name, target);
+    }
+    let _ = (parent, target);
+    ax_err!(Unsupported)
+}
+
+pub(crate) fn read_link(path: &str) -> AxResult<String> {
+    let node = lookup(None, path)?;
+    #[cfg(feature = "ramfs")]
+    if let Some(link) = node.as_any().downcast_ref::<fs::ramfs::SymlinkNode>() {
+        return Ok(link.target().into());
+    }
+    let _ = node;
+    ax_err!(InvalidInput)
+}
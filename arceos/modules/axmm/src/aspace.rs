@@ -9,9 +9,10 @@ use memory_addr::{
     is_aligned_4k, pa, MemoryAddr, PageIter4K, PhysAddr, VirtAddr, VirtAddrRange, PAGE_SIZE_4K,
 };
 use memory_set::{MemoryArea, MemorySet};
-use crate::backend::Backend;
+use crate::backend::{alloc_frame, dealloc_frame, Backend, SharedFrames};
 use crate::paging_err_to_ax_err;
 use crate::mapping_err_to_ax_err;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
 /// The virtual memory address space.
@@ -77,6 +78,53 @@ impl AddrSpace {
         Ok(())
     }
 
+    /// Copies the memory areas of another address space into this one, as
+    /// `fork` does.
+    ///
+    /// Allocation areas get private copies of the pages populated in `other`,
+    /// pages not populated yet stay lazy. Linear and shared areas map the same
+    /// physical frames as in `other`.
+    ///
+    /// Returns an error if an area overlaps the existing ones, or there is no
+    /// memory for the copies.
+    pub fn copy_areas_from(&mut self, other: &AddrSpace) -> AxResult {
+        for area in other.areas.iter() {
+            let backend = match area.backend() {
+                Backend::Alloc { .. } => Backend::new_alloc(false),
+                backend => backend.clone(),
+            };
+            let is_alloc = matches!(backend, Backend::Alloc { .. });
+            let new_area = MemoryArea::new(area.start(), area.size(), area.flags(), backend);
+            self.areas
+                .map(new_area, &mut self.pt, false)
+                .map_err(mapping_err_to_ax_err)?;
+            if !is_alloc {
+                continue;
+            }
+            for vaddr in PageIter4K::new(area.start(), area.end()).unwrap() {
+                let Ok((old_frame, flags, _)) = other.pt.query(vaddr) else {
+                    continue;
+                };
+                let frame = alloc_frame(false).ok_or(AxError::NoMemory)?;
+                unsafe {
+                    core::ptr::copy_nonoverlapping(
+                        phys_to_virt(old_frame.align_down_4k()).as_ptr(),
+                        phys_to_virt(frame).as_mut_ptr(),
+                        PAGE_SIZE_4K,
+                    );
+                }
+                match self.pt.remap(vaddr, frame, flags) {
+                    Ok((_, tlb)) => tlb.ignore(),
+                    Err(e) => {
+                        dealloc_frame(frame);
+                        return Err(paging_err_to_ax_err(e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Finds a free area that can accommodate the given size.
     ///
     /// The search starts from the given hint address, and the area should be within the given limit range.
@@ -158,8 +206,39 @@ impl AddrSpace {
         Ok(())
     }
 
+    /// Add a new mapping of the given shared frames at `start`.
+    ///
+    /// The same frames can be mapped in several address spaces, changes made
+    /// through one mapping are visible through all others.
+    ///
+    /// Returns an error if the address range is out of the address space or not
+    /// aligned.
+    pub fn map_shared(
+        &mut self,
+        start: VirtAddr,
+        frames: Arc<SharedFrames>,
+        flags: MappingFlags,
+    ) -> AxResult {
+        let size = frames.size();
+        if !self.contains_range(start, size) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        if !start.is_aligned_4k() {
+            return ax_err!(InvalidInput, "address not aligned");
+        }
+
+        let area = MemoryArea::new(start, size, flags, Backend::new_shared(frames, start));
+        self.areas
+            .map(area, &mut self.pt, false)
+            .map_err(mapping_err_to_ax_err)?;
+        Ok(())
+    }
+
     /// Removes mappings within the specified virtual address range.
     ///
+    /// Areas that partially overlap the range are split, and the physical
+    /// frames backing the removed part are freed.
+    ///
     /// Returns an error if the address range is out of the address space or not
     /// aligned.
     pub fn unmap(&mut self, start: VirtAddr, size: usize) -> AxResult {
@@ -170,13 +249,37 @@ impl AddrSpace {
             return ax_err!(InvalidInput, "address not aligned");
         }
 
-        self.pt
-            .unmap_region(start, size, true)
-            .map_err(paging_err_to_ax_err)?
-            .ignore();
+        self.areas
+            .unmap(start, size, &mut self.pt)
+            .map_err(mapping_err_to_ax_err)?;
         Ok(())
     }
 
+    /// Checks whether the given range is fully covered by areas that allow
+    /// the given access.
+    ///
+    /// The pages don't need to be populated yet, since accessing them is
+    /// handled by [`AddrSpace::handle_page_fault`].
+    pub fn check_region_access(&self, range: VirtAddrRange, access_flags: MappingFlags) -> bool {
+        let mut start = range.start;
+        if start >= range.end {
+            return true;
+        }
+        for area in self.areas.iter() {
+            if area.end() <= start {
+                continue;
+            }
+            if area.start() > start || !area.flags().contains(access_flags) {
+                return false;
+            }
+            start = area.end();
+            if start >= range.end {
+                return true;
+            }
+        }
+        false
+    }
+
     /// To process data in this area with the given function.
     ///
     /// Now it supports reading and writing data in the given interval.
@@ -237,6 +340,9 @@ impl AddrSpace {
 
     /// Updates mapping within the specified virtual address range.
     ///
+    /// The flags of the areas in the range are updated as well, so that page
+    /// faults are checked against the new flags.
+    ///
     /// Returns an error if the address range is out of the address space or not
     /// aligned.
     pub fn protect(&mut self, start: VirtAddr, size: usize, flags: MappingFlags) -> AxResult {
@@ -247,6 +353,10 @@ impl AddrSpace {
             return ax_err!(InvalidInput, "address not aligned");
         }
 
+        self.areas
+            .protect(start, size, |_| Some(flags), &mut self.pt)
+            .map_err(mapping_err_to_ax_err)?;
+        // The range may not be fully covered by areas (e.g. the kernel space).
         self.pt
             .protect_region(start, size, flags, true)
             .map_err(paging_err_to_ax_err)?
@@ -254,6 +364,36 @@ impl AddrSpace {
         Ok(())
     }
 
+    /// Makes the mapped pages within the specified range read-only in the page
+    /// table, while the areas keep their flags.
+    ///
+    /// The first write to each page then triggers a page fault, which gives
+    /// the page a private writable copy (copy-on-write).
+    ///
+    /// Returns an error if the address range is out of the address space or not
+    /// aligned.
+    pub fn mark_copy_on_write(&mut self, start: VirtAddr, size: usize) -> AxResult {
+        if !self.contains_range(start, size) {
+            return ax_err!(InvalidInput, "address out of range");
+        }
+        if !start.is_aligned_4k() || !is_aligned_4k(size) {
+            return ax_err!(InvalidInput, "address not aligned");
+        }
+
+        for vaddr in PageIter4K::new(start, start + size).unwrap() {
+            if let Ok((_, flags, _)) = self.pt.query(vaddr) {
+                if flags.contains(MappingFlags::WRITE) {
+                    self.pt
+                        .protect(vaddr, flags - MappingFlags::WRITE)
+                        .map_err(paging_err_to_ax_err)?
+                        .1
+                        .flush();
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Handles a page fault at the given address.
     ///
     /// `access_flags` indicates the access type that caused the page fault.
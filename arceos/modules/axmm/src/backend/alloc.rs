@@ -1,11 +1,11 @@
 use axalloc::global_allocator;
 use axhal::mem::{phys_to_virt, virt_to_phys};
 use axhal::paging::{MappingFlags, PageSize, PageTable};
-use memory_addr::{PageIter4K, PhysAddr, VirtAddr, PAGE_SIZE_4K};
+use memory_addr::{MemoryAddr, PageIter4K, PhysAddr, VirtAddr, PAGE_SIZE_4K};
 
 use super::Backend;
 
-fn alloc_frame(zeroed: bool) -> Option<PhysAddr> {
+pub(crate) fn alloc_frame(zeroed: bool) -> Option<PhysAddr> {
     let vaddr = VirtAddr::from(global_allocator().alloc_pages(1, PAGE_SIZE_4K).ok()?);
     if zeroed {
         unsafe { core::ptr::write_bytes(vaddr.as_mut_ptr(), 0, PAGE_SIZE_4K) };
@@ -14,7 +14,7 @@ fn alloc_frame(zeroed: bool) -> Option<PhysAddr> {
     Some(paddr)
 }
 
-fn dealloc_frame(frame: PhysAddr) {
+pub(crate) fn dealloc_frame(frame: PhysAddr) {
     let vaddr = phys_to_virt(frame);
     global_allocator().dealloc_pages(vaddr.as_usize(), 1);
 }
@@ -92,7 +92,15 @@ impl Backend {
         pt: &mut PageTable,
         populate: bool,
     ) -> bool {
-        if populate {
+        if let Ok((frame, flags, _)) = pt.query(vaddr) {
+            // The page is present, so only a write to a copy-on-write page
+            // can be handled.
+            if orig_flags.contains(MappingFlags::WRITE) && !flags.contains(MappingFlags::WRITE) {
+                Self::handle_cow_fault(vaddr, frame.align_down_4k(), orig_flags, pt)
+            } else {
+                false
+            }
+        } else if populate {
             false // Populated mappings should not trigger page faults.
         } else if let Some(frame) = alloc_frame(true) {
             // Allocate a physical frame lazily and map it to the fault address.
@@ -105,4 +113,35 @@ impl Backend {
             false
         }
     }
+
+    /// Copies the read-only page at `vaddr` to a new frame and maps it with
+    /// `orig_flags`, then releases the old frame.
+    fn handle_cow_fault(
+        vaddr: VirtAddr,
+        old_frame: PhysAddr,
+        orig_flags: MappingFlags,
+        pt: &mut PageTable,
+    ) -> bool {
+        let Some(new_frame) = alloc_frame(false) else {
+            return false;
+        };
+        unsafe {
+            core::ptr::copy_nonoverlapping(
+                phys_to_virt(old_frame).as_ptr(),
+                phys_to_virt(new_frame).as_mut_ptr(),
+                PAGE_SIZE_4K,
+            );
+        }
+        match pt.remap(vaddr, new_frame, orig_flags) {
+            Ok((_, tlb)) => {
+                tlb.flush();
+                dealloc_frame(old_frame);
+                true
+            }
+            Err(_) => {
+                dealloc_frame(new_frame);
+                false
+            }
+        }
+    }
 }
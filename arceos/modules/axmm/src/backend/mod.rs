@@ -1,21 +1,28 @@
 //! Memory mapping backends.
 #![allow(dead_code)]
 
+use ::alloc::sync::Arc;
 use axhal::paging::{MappingFlags, PageTable};
 use memory_addr::VirtAddr;
 use memory_set::MappingBackend;
 
 mod alloc;
 mod linear;
+mod shared;
+
+pub use shared::SharedFrames;
+pub(crate) use self::alloc::{alloc_frame, dealloc_frame};
 
 /// A unified enum type for different memory mapping backends.
 ///
-/// Currently, two backends are implemented:
+/// Currently, three backends are implemented:
 ///
 /// - **Linear**: used for linear mappings. The target physical frames are
 ///   contiguous and their addresses should be known when creating the mapping.
 /// - **Allocation**: used in general, or for lazy mappings. The target physical
 ///   frames are obtained from the global allocator.
+/// - **Shared**: used for memory shared between address spaces. The target
+///   physical frames are [`SharedFrames`] allocated in advance.
 #[derive(Clone)]
 pub enum Backend {
     /// Linear mapping backend.
@@ -37,6 +44,16 @@ pub enum Backend {
         /// Whether to populate the physical frames when creating the mapping.
         populate: bool,
     },
+    /// Shared mapping backend.
+    ///
+    /// The page at `base + i * PAGE_SIZE_4K` is mapped to the `i`-th frame of
+    /// `frames`. The frames are only freed when no mapping refers to them.
+    Shared {
+        /// The frames to map.
+        frames: Arc<SharedFrames>,
+        /// The virtual address that the first frame is mapped to.
+        base: VirtAddr,
+    },
 }
 
 impl MappingBackend for Backend {
@@ -47,6 +64,9 @@ impl MappingBackend for Backend {
         match *self {
             Self::Linear { pa_va_offset } => self.map_linear(start, size, flags, pt, pa_va_offset),
             Self::Alloc { populate } => self.map_alloc(start, size, flags, pt, populate),
+            Self::Shared { ref frames, base } => {
+                self.map_shared(start, size, flags, pt, frames, base)
+            }
         }
     }
 
@@ -54,6 +74,7 @@ impl MappingBackend for Backend {
         match *self {
             Self::Linear { pa_va_offset } => self.unmap_linear(start, size, pt, pa_va_offset),
             Self::Alloc { populate } => self.unmap_alloc(start, size, pt, populate),
+            Self::Shared { .. } => self.unmap_shared(start, size, pt),
         }
     }
 
@@ -83,6 +104,7 @@ impl Backend {
             Self::Alloc { populate } => {
                 self.handle_page_fault_alloc(vaddr, orig_flags, page_table, populate)
             }
+            Self::Shared { .. } => false, // Shared mappings are always populated.
         }
     }
 }
@@ -0,0 +1,91 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use axerrno::{AxError, AxResult};
+use axhal::paging::{MappingFlags, PageSize, PageTable};
+use memory_addr::{PageIter4K, PhysAddr, VirtAddr, PAGE_SIZE_4K};
+
+use super::alloc::{alloc_frame, dealloc_frame};
+use super::Backend;
+
+/// Physical frames that can be mapped into several address spaces at once.
+///
+/// The frames are allocated zeroed when created, and freed when the last
+/// reference (held by the mappings of them) is dropped.
+pub struct SharedFrames {
+    frames: Vec<PhysAddr>,
+}
+
+impl SharedFrames {
+    /// Allocates `num_pages` zeroed frames.
+    pub fn new(num_pages: usize) -> AxResult<Arc<Self>> {
+        let mut frames = Vec::with_capacity(num_pages);
+        for _ in 0..num_pages {
+            match alloc_frame(true) {
+                Some(frame) => frames.push(frame),
+                None => {
+                    // the already allocated frames are freed on drop
+                    drop(Self { frames });
+                    return Err(AxError::NoMemory);
+                }
+            }
+        }
+        Ok(Arc::new(Self { frames }))
+    }
+
+    /// Returns the number of frames.
+    pub fn num_pages(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Returns the size of all frames in bytes.
+    pub fn size(&self) -> usize {
+        self.frames.len() * PAGE_SIZE_4K
+    }
+}
+
+impl Drop for SharedFrames {
+    fn drop(&mut self) {
+        for &frame in &self.frames {
+            dealloc_frame(frame);
+        }
+    }
+}
+
+impl Backend {
+    /// Creates a new shared mapping backend, which maps `frames` starting
+    /// from the virtual address `base`.
+    pub const fn new_shared(frames: Arc<SharedFrames>, base: VirtAddr) -> Self {
+        Self::Shared { frames, base }
+    }
+
+    pub(crate) fn map_shared(
+        &self,
+        start: VirtAddr,
+        size: usize,
+        flags: MappingFlags,
+        pt: &mut PageTable,
+        frames: &SharedFrames,
+        base: VirtAddr,
+    ) -> bool {
+        debug!("map_shared: [{:#x}, {:#x}) {:?}", start, start + size, flags);
+        for addr in PageIter4K::new(start, start + size).unwrap() {
+            let Some(&frame) = frames.frames.get((addr - base) / PAGE_SIZE_4K) else {
+                return false;
+            };
+            match pt.map(addr, frame, PageSize::Size4K, flags) {
+                Ok(tlb) => tlb.ignore(), // TLB flush on map is unnecessary, as there are no outdated mappings.
+                Err(_) => return false,
+            }
+        }
+        true
+    }
+
+    pub(crate) fn unmap_shared(&self, start: VirtAddr, size: usize, pt: &mut PageTable) -> bool {
+        debug!("unmap_shared: [{:#x}, {:#x})", start, start + size);
+        // the frames are owned by `SharedFrames`, only remove the mappings
+        pt.unmap_region(start, size, true)
+            .map(|tlb| tlb.ignore())
+            .is_ok()
+    }
+}
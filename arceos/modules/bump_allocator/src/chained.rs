@@ -0,0 +1,119 @@
+use allocator::{AllocError, AllocResult, BaseAllocator, ByteAllocator, PageAllocator};
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+use crate::EarlyAllocator;
+
+/// An [`EarlyAllocator`] that spills over to a fallback allocator once its
+/// arena is exhausted.
+///
+/// Allocations are tried in the early arena first, and on
+/// [`AllocError::NoMemory`] delegated to the fallback. Frees are routed by
+/// address: memory inside the early arena goes back to it, everything else
+/// to the fallback.
+///
+/// Created by [`EarlyAllocator::with_fallback`].
+pub struct ChainedAllocator<const PAGE_SIZE: usize, B> {
+    early: EarlyAllocator<PAGE_SIZE>,
+    fallback: B,
+}
+
+impl<const PAGE_SIZE: usize> EarlyAllocator<PAGE_SIZE> {
+    /// Chains `next` behind this allocator, which takes over once this
+    /// arena is exhausted.
+    pub fn with_fallback<B>(self, next: B) -> ChainedAllocator<PAGE_SIZE, B> {
+        ChainedAllocator {
+            early: self,
+            fallback: next,
+        }
+    }
+}
+
+impl<const PAGE_SIZE: usize, B> ChainedAllocator<PAGE_SIZE, B> {
+    /// Returns the early allocator.
+    pub fn early(&self) -> &EarlyAllocator<PAGE_SIZE> {
+        &self.early
+    }
+
+    /// Returns the fallback allocator.
+    pub fn fallback(&self) -> &B {
+        &self.fallback
+    }
+}
+
+/// Calls `fallback` if `res` failed because the early arena is full.
+fn or_fallback<T>(res: AllocResult<T>, fallback: impl FnOnce() -> AllocResult<T>) -> AllocResult<T> {
+    match res {
+        Err(AllocError::NoMemory) => fallback(),
+        res => res,
+    }
+}
+
+impl<const PAGE_SIZE: usize, B: BaseAllocator> BaseAllocator for ChainedAllocator<PAGE_SIZE, B> {
+    fn init(&mut self, start: usize, size: usize) {
+        self.early.init(start, size);
+    }
+
+    fn add_memory(&mut self, start: usize, size: usize) -> AllocResult {
+        // the early arena can't grow, new memory goes to the fallback
+        self.fallback.add_memory(start, size)
+    }
+}
+
+impl<const PAGE_SIZE: usize, B: ByteAllocator> ByteAllocator for ChainedAllocator<PAGE_SIZE, B> {
+    fn alloc(&mut self, layout: Layout) -> AllocResult<NonNull<u8>> {
+        or_fallback(self.early.alloc(layout), || self.fallback.alloc(layout))
+    }
+
+    fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+        if self.early.owns(ptr) {
+            self.early.dealloc(ptr, layout);
+        } else {
+            self.fallback.dealloc(ptr, layout);
+        }
+    }
+
+    fn total_bytes(&self) -> usize {
+        self.early.total_bytes() + self.fallback.total_bytes()
+    }
+
+    fn used_bytes(&self) -> usize {
+        self.early.used_bytes() + self.fallback.used_bytes()
+    }
+
+    fn available_bytes(&self) -> usize {
+        self.early.available_bytes() + self.fallback.available_bytes()
+    }
+}
+
+/// The fallback must use the same page size as the early allocator.
+impl<const PAGE_SIZE: usize, B: PageAllocator> PageAllocator for ChainedAllocator<PAGE_SIZE, B> {
+    const PAGE_SIZE: usize = PAGE_SIZE;
+
+    fn alloc_pages(&mut self, num_pages: usize, align_pow2: usize) -> AllocResult<usize> {
+        debug_assert_eq!(B::PAGE_SIZE, PAGE_SIZE);
+        or_fallback(self.early.alloc_pages(num_pages, align_pow2), || {
+            self.fallback.alloc_pages(num_pages, align_pow2)
+        })
+    }
+
+    fn dealloc_pages(&mut self, pos: usize, num_pages: usize) {
+        if self.early.usable_range().contains(&pos) {
+            self.early.dealloc_pages(pos, num_pages);
+        } else {
+            self.fallback.dealloc_pages(pos, num_pages);
+        }
+    }
+
+    fn total_pages(&self) -> usize {
+        self.early.total_pages() + self.fallback.total_pages()
+    }
+
+    fn used_pages(&self) -> usize {
+        self.early.used_pages() + self.fallback.used_pages()
+    }
+
+    fn available_pages(&self) -> usize {
+        self.early.available_pages() + self.fallback.available_pages()
+    }
+}
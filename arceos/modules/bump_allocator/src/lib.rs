@@ -1,13 +1,21 @@
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
 use allocator::{BaseAllocator, ByteAllocator, PageAllocator, AllocResult, AllocError};
 use core::alloc::Layout;
 use core::ptr::NonNull;
 
-const POOL_SIZE: usize = 1024 * 1024; // 1MB
+mod chained;
+
+#[cfg(test)]
+mod tests;
+
+pub use chained::ChainedAllocator;
+
 const PAGE_SIZE: usize = 4096;
 
-static mut POOL: [u8; POOL_SIZE] = [0; POOL_SIZE];
+/// The byte pattern filled into guard pages allocated by
+/// [`EarlyAllocator::alloc_pages_with_guard`].
+pub const GUARD_PATTERN: u8 = 0xfd;
 
 /// Early memory allocator
 /// Use it before formal bytes-allocator and pages-allocator can work!
@@ -21,7 +29,8 @@ static mut POOL: [u8; POOL_SIZE] = [0; POOL_SIZE];
 ///
 /// For bytes area, 'count' records number of allocations.
 /// When it goes down to ZERO, free bytes-used area.
-/// For pages area, it will never be freed!
+/// For pages area, only the most recently allocated block (the one at
+/// 'p_pos') can be freed, other frees just decrease the counter.
 ///
 // ... existing code ...
 pub struct EarlyAllocator<const PAGE_SIZE: usize> {
@@ -46,6 +55,122 @@ impl<const PAGE_SIZE: usize> EarlyAllocator<PAGE_SIZE> {
             inited: false,
         }
     }
+
+    /// Allocates `num_pages` contiguous pages with a guard page directly
+    /// below them, and returns the address just above the guard page.
+    ///
+    /// This allocator doesn't manage page tables, so the guard page can't be
+    /// unmapped. Instead it is filled with [`GUARD_PATTERN`], which can be
+    /// checked later by [`guard_intact`](Self::guard_intact). The guard page
+    /// is not counted in `used_pages`.
+    pub fn alloc_pages_with_guard(
+        &mut self,
+        num_pages: usize,
+        align_pow2: usize,
+    ) -> AllocResult<usize> {
+        let pos = self.pages_below_p_pos(num_pages, align_pow2)?;
+        let guard = pos.checked_sub(PAGE_SIZE).ok_or(AllocError::NoMemory)?;
+        if guard < self.b_pos {
+            return Err(AllocError::NoMemory);
+        }
+        // SAFETY: the guard page lies in the free area of the arena
+        unsafe { core::ptr::write_bytes(guard as *mut u8, GUARD_PATTERN, PAGE_SIZE) };
+        self.p_pos = guard;
+        self.used_pages += num_pages;
+        Ok(pos)
+    }
+
+    /// Computes the start of `num_pages` pages right below `p_pos`, aligned to
+    /// `align_pow2` pages, without changing the allocator.
+    ///
+    /// Returns [`AllocError::InvalidParam`] if `num_pages` is 0 or
+    /// `align_pow2` is not a power of two (including 0).
+    fn pages_below_p_pos(&self, num_pages: usize, align_pow2: usize) -> AllocResult<usize> {
+        if num_pages == 0 || !align_pow2.is_power_of_two() {
+            return Err(AllocError::InvalidParam);
+        }
+        if !self.inited { return Err(AllocError::NoMemory); }
+        let size = num_pages.checked_mul(PAGE_SIZE).ok_or(AllocError::NoMemory)?;
+        let align = align_pow2.checked_mul(PAGE_SIZE).ok_or(AllocError::InvalidParam)?;
+        let pos = self.p_pos.checked_sub(size).ok_or(AllocError::NoMemory)?;
+        // 向下对齐
+        Ok(pos & !(align - 1))
+    }
+
+    /// Returns whether the byte cursor and the page cursor have met, i.e. less
+    /// than a page is left between them and no more pages can be allocated.
+    pub fn is_exhausted(&self) -> bool {
+        self.inited && self.p_pos.saturating_sub(self.b_pos) < PAGE_SIZE
+    }
+
+    /// Returns the address range of the arena managed by this allocator.
+    pub fn usable_range(&self) -> core::ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// Checks whether `ptr` points into the arena of this allocator, i.e.
+    /// whether it may have been allocated by it.
+    pub fn owns(&self, ptr: NonNull<u8>) -> bool {
+        self.usable_range().contains(&(ptr.as_ptr() as usize))
+    }
+
+    /// Called when an allocation fails with [`AllocError::NoMemory`], reports
+    /// it if the failure is because the two ends of the arena have met.
+    fn check_exhausted(&self) {
+        if self.is_exhausted() {
+            #[cfg(feature = "log")]
+            log::warn!(
+                "EarlyAllocator exhausted: b_pos {:#x} meets p_pos {:#x}",
+                self.b_pos,
+                self.p_pos
+            );
+        }
+    }
+
+    /// Logs the layout of the arena in a single line, for diagnosing boot
+    /// memory issues.
+    #[cfg(feature = "log")]
+    pub fn debug_dump(&self) {
+        log::debug!("{:?}", self);
+    }
+
+    /// Checks whether the guard page below `pos`, which is returned by
+    /// [`alloc_pages_with_guard`](Self::alloc_pages_with_guard), still holds
+    /// [`GUARD_PATTERN`], i.e. nothing has overflowed into it.
+    ///
+    /// Returns `false` if the page below `pos` is not within the allocated
+    /// pages of the arena, so it can't be a guard page.
+    pub fn guard_intact(&self, pos: usize) -> bool {
+        let Some(guard) = pos.checked_sub(PAGE_SIZE) else {
+            return false;
+        };
+        if guard < self.p_pos || pos > self.end {
+            return false;
+        }
+        // SAFETY: the page is within the page area of the arena, which is
+        // valid memory owned by this allocator
+        let guard = unsafe { core::slice::from_raw_parts(guard as *const u8, PAGE_SIZE) };
+        guard.iter().all(|&b| b == GUARD_PATTERN)
+    }
+}
+
+impl<const PAGE_SIZE: usize> core::fmt::Debug for EarlyAllocator<PAGE_SIZE> {
+    /// Shows both cursors and the free gap between them, e.g.
+    /// `EarlyAllocator { start: 0x1000, end: 0x9000, b_pos: 0x1010, ... }`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "EarlyAllocator {{ start: {:#x}, end: {:#x}, b_pos: {:#x}, p_pos: {:#x}, \
+             used_bytes: {}, used_pages: {}, free: {:#x} }}",
+            self.start,
+            self.end,
+            self.b_pos,
+            self.p_pos,
+            self.used_bytes,
+            self.used_pages,
+            self.p_pos.saturating_sub(self.b_pos),
+        )
+    }
 }
 
 impl<const PAGE_SIZE: usize> BaseAllocator for EarlyAllocator<PAGE_SIZE> {
@@ -70,17 +195,21 @@ impl<const PAGE_SIZE: usize> ByteAllocator for EarlyAllocator<PAGE_SIZE> {
         if !self.inited { return Err(AllocError::NoMemory); }
         let align = layout.align();
         let size = layout.size();
-        let pos = (self.b_pos + align - 1) & !(align - 1);
-        if pos + size > self.p_pos {
+        // b_pos 是真实地址，直接对它对齐；对齐量很大时可能溢出，一律视为内存不足
+        let end = self
+            .b_pos
+            .checked_next_multiple_of(align)
+            .and_then(|pos| pos.checked_add(size))
+            .filter(|&end| end <= self.p_pos);
+        let Some(end) = end else {
+            self.check_exhausted();
             return Err(AllocError::NoMemory);
-        }
-        self.b_pos = pos + size;
+        };
+        let pos = end - size;
+        self.b_pos = end;
         self.used_bytes += size;
-        // SAFETY: POOL is static and we only hand out unique slices
-        let offset = pos - self.start;
-        unsafe {
-            Ok(NonNull::new_unchecked(POOL.as_mut_ptr().add(offset)))
-        }
+        // 直接返回 [start, end) 区间内的地址
+        NonNull::new(pos as *mut u8).ok_or(AllocError::NoMemory)
     }
 
     fn dealloc(&mut self, _ptr: NonNull<u8>, layout: Layout) {
@@ -105,12 +234,9 @@ impl<const PAGE_SIZE: usize> PageAllocator for EarlyAllocator<PAGE_SIZE> {
     const PAGE_SIZE: usize = PAGE_SIZE;
 
     fn alloc_pages(&mut self, num_pages: usize, align_pow2: usize) -> AllocResult<usize> {
-        if !self.inited { return Err(AllocError::NoMemory); }
-        let size = num_pages * PAGE_SIZE;
-        let mut new_p_pos = self.p_pos.checked_sub(size).ok_or(AllocError::NoMemory)?;
-        // 向下对齐
-        new_p_pos = new_p_pos & !(align_pow2 * PAGE_SIZE - 1);
+        let new_p_pos = self.pages_below_p_pos(num_pages, align_pow2)?;
         if new_p_pos < self.b_pos {
+            self.check_exhausted();
             return Err(AllocError::NoMemory);
         }
         self.p_pos = new_p_pos;
@@ -118,8 +244,19 @@ impl<const PAGE_SIZE: usize> PageAllocator for EarlyAllocator<PAGE_SIZE> {
         Ok(self.p_pos)
     }
 
-    fn dealloc_pages(&mut self, _pos: usize, num_pages: usize) {
-        // bump分配器通常不支持单独回收
+    fn dealloc_pages(&mut self, pos: usize, num_pages: usize) {
+        // 只有最后分配的（位于 p_pos 的）页块可以真正回收，其余只减少计数
+        if pos == self.p_pos {
+            // 页数不对时不能让 p_pos 越过区域末尾，否则之后会分配出区域外的页
+            let new_p_pos = num_pages
+                .checked_mul(PAGE_SIZE)
+                .and_then(|size| pos.checked_add(size));
+            debug_assert!(
+                new_p_pos.is_some_and(|p| p <= self.end),
+                "dealloc_pages: {num_pages} pages at {pos:#x} go past the end of the region"
+            );
+            self.p_pos = new_p_pos.map_or(self.end, |p| p.min(self.end));
+        }
         self.used_pages = self.used_pages.saturating_sub(num_pages);
     }
 
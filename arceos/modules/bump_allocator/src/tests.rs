@@ -0,0 +1,293 @@
+use std::alloc::{alloc, dealloc, Layout};
+
+use allocator::{BaseAllocator, ByteAllocator, PageAllocator};
+
+use crate::*;
+
+const PAGE_SIZE: usize = 0x1000;
+
+/// A page-aligned memory region on the host heap, used as the arena.
+struct Arena {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+impl Arena {
+    fn new(num_pages: usize) -> Self {
+        let layout = Layout::from_size_align(num_pages * PAGE_SIZE, PAGE_SIZE).unwrap();
+        let ptr = unsafe { alloc(layout) };
+        assert!(!ptr.is_null());
+        Self { ptr, layout }
+    }
+
+    fn start(&self) -> usize {
+        self.ptr as usize
+    }
+
+    fn size(&self) -> usize {
+        self.layout.size()
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr, self.layout) };
+    }
+}
+
+fn new_allocator(arena: &Arena) -> EarlyAllocator<PAGE_SIZE> {
+    let mut allocator = EarlyAllocator::new();
+    allocator.init(arena.start(), arena.size());
+    allocator
+}
+
+#[test]
+fn test_alloc_pages_with_guard() {
+    let arena = Arena::new(16);
+    let mut allocator = new_allocator(&arena);
+
+    let pos = allocator.alloc_pages_with_guard(4, 1).unwrap();
+    // The region is 4 pages long and ends at the top of the arena, and the
+    // guard page sits directly below it.
+    assert_eq!(pos, arena.start() + arena.size() - 4 * PAGE_SIZE);
+    assert_eq!(allocator.used_pages(), 4);
+    assert_eq!(allocator.available_pages(), 16 - 5);
+    assert!(allocator.guard_intact(pos));
+
+    // The pages themselves are usable, and writing them leaves the guard intact.
+    unsafe { core::ptr::write_bytes(pos as *mut u8, 0, 4 * PAGE_SIZE) };
+    assert!(allocator.guard_intact(pos));
+
+    // An overflow below the region is detected.
+    unsafe { *((pos - 1) as *mut u8) = 0 };
+    assert!(!allocator.guard_intact(pos));
+
+    // The next allocation goes below the guard page.
+    let next = allocator.alloc_pages(1, 1).unwrap();
+    assert_eq!(next, pos - 2 * PAGE_SIZE);
+    assert_eq!(allocator.alloc_pages_with_guard(10, 1).err(), Some(AllocError::NoMemory));
+
+    // Positions whose page below is outside the allocated pages are rejected
+    // without being read.
+    assert!(!allocator.guard_intact(0));
+    assert!(!allocator.guard_intact(usize::MAX));
+    assert!(!allocator.guard_intact(arena.start()));
+    assert!(!allocator.guard_intact(next));
+}
+
+#[test]
+fn test_two_allocators() {
+    let arena1 = Arena::new(4);
+    let arena2 = Arena::new(4);
+    let mut a1 = new_allocator(&arena1);
+    let mut a2 = new_allocator(&arena2);
+    let layout = Layout::from_size_align(100, 8).unwrap();
+
+    let mut ptrs = Vec::new();
+    for i in 0..10u8 {
+        let p1 = a1.alloc(layout).unwrap().as_ptr();
+        let p2 = a2.alloc(layout).unwrap().as_ptr();
+        // Each allocator hands out memory from its own region.
+        assert!((arena1.start()..arena1.start() + arena1.size()).contains(&(p1 as usize)));
+        assert!((arena2.start()..arena2.start() + arena2.size()).contains(&(p2 as usize)));
+        unsafe {
+            core::ptr::write_bytes(p1, i, 100);
+            core::ptr::write_bytes(p2, !i, 100);
+        }
+        ptrs.push((p1, p2));
+    }
+    assert_eq!(a1.used_bytes(), 1000);
+    assert_eq!(a2.used_bytes(), 1000);
+
+    // Writes through one allocator never clobber the other one's memory.
+    for (i, &(p1, p2)) in ptrs.iter().enumerate() {
+        let s1 = unsafe { core::slice::from_raw_parts(p1, 100) };
+        let s2 = unsafe { core::slice::from_raw_parts(p2, 100) };
+        assert!(s1.iter().all(|&b| b == i as u8));
+        assert!(s2.iter().all(|&b| b == !(i as u8)));
+    }
+}
+
+#[test]
+fn test_alloc_pages_invalid_param() {
+    let arena = Arena::new(16);
+    let mut allocator = new_allocator(&arena);
+    let first = allocator.alloc_pages(1, 1).unwrap();
+
+    assert_eq!(allocator.alloc_pages(0, 1), Err(AllocError::InvalidParam));
+    assert_eq!(allocator.alloc_pages(1, 0), Err(AllocError::InvalidParam));
+    assert_eq!(allocator.alloc_pages(1, 3), Err(AllocError::InvalidParam));
+    assert_eq!(allocator.alloc_pages_with_guard(0, 1), Err(AllocError::InvalidParam));
+
+    // The rejected calls leave the allocator untouched.
+    assert_eq!(allocator.used_pages(), 1);
+    assert_eq!(allocator.available_pages(), 15);
+    assert_eq!(allocator.alloc_pages(1, 1).unwrap(), first - PAGE_SIZE);
+}
+
+#[test]
+fn test_dealloc_pages_lifo() {
+    let arena = Arena::new(16);
+    let mut allocator = new_allocator(&arena);
+
+    let bottom = allocator.alloc_pages(2, 1).unwrap();
+    let top = allocator.alloc_pages(3, 1).unwrap();
+    assert_eq!(allocator.available_pages(), 16 - 5);
+
+    // Freeing a block that is not the topmost only updates the counter.
+    allocator.dealloc_pages(bottom, 2);
+    assert_eq!(allocator.used_pages(), 3);
+    assert_eq!(allocator.available_pages(), 16 - 5);
+
+    // Freeing the topmost block gives its pages back.
+    allocator.dealloc_pages(top, 3);
+    assert_eq!(allocator.used_pages(), 0);
+    assert_eq!(allocator.available_pages(), 16 - 2);
+
+    // The reclaimed pages are handed out again.
+    assert_eq!(allocator.alloc_pages(3, 1).unwrap(), top);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "past the end of the region")]
+fn test_dealloc_pages_past_end() {
+    let arena = Arena::new(16);
+    let mut allocator = new_allocator(&arena);
+
+    // Freeing more pages than were allocated must not move p_pos past the end.
+    let top = allocator.alloc_pages(1, 1).unwrap();
+    allocator.dealloc_pages(top, 2);
+}
+
+#[test]
+fn test_is_exhausted() {
+    let arena = Arena::new(8);
+    let mut allocator = new_allocator(&arena);
+    assert!(!allocator.is_exhausted());
+
+    // Fill the arena from both ends until the cursors meet.
+    let layout = Layout::from_size_align(PAGE_SIZE / 2, 8).unwrap();
+    for _ in 0..4 {
+        allocator.alloc(layout).unwrap();
+    }
+    for _ in 0..5 {
+        allocator.alloc_pages(1, 1).unwrap();
+    }
+    assert!(!allocator.is_exhausted());
+    allocator.alloc(layout).unwrap();
+    assert!(allocator.is_exhausted());
+
+    // Both ends report plain NoMemory once full; the byte end can still use
+    // the last half page.
+    assert_eq!(allocator.alloc_pages(1, 1), Err(AllocError::NoMemory));
+    allocator.alloc(layout).unwrap();
+    assert_eq!(allocator.alloc(layout), Err(AllocError::NoMemory));
+}
+
+#[test]
+fn test_chained_allocator() {
+    let early_arena = Arena::new(4);
+    let fallback_arena = Arena::new(16);
+    let mut allocator = new_allocator(&early_arena).with_fallback(new_allocator(&fallback_arena));
+    let in_arena = |arena: &Arena, pos: usize| (arena.start()..arena.start() + arena.size()).contains(&pos);
+    let in_early = |pos: usize| in_arena(&early_arena, pos);
+    let in_fallback = |pos: usize| in_arena(&fallback_arena, pos);
+    assert_eq!(allocator.total_pages(), 20);
+
+    // The early arena is used first.
+    let early_pages = allocator.alloc_pages(4, 1).unwrap();
+    assert!(in_early(early_pages));
+    assert!(allocator.early().is_exhausted());
+
+    // Once it is exhausted, allocations come from the fallback.
+    let pages = allocator.alloc_pages(2, 1).unwrap();
+    assert!(in_fallback(pages));
+    let layout = Layout::from_size_align(100, 8).unwrap();
+    let ptr = allocator.alloc(layout).unwrap();
+    assert!(in_fallback(ptr.as_ptr() as usize));
+    assert_eq!(allocator.used_pages(), 6);
+    assert_eq!(allocator.used_bytes(), 100);
+
+    // Frees go back to the allocator owning the memory.
+    allocator.dealloc(ptr, layout);
+    assert_eq!(allocator.fallback().used_bytes(), 0);
+    allocator.dealloc_pages(pages, 2);
+    assert_eq!(allocator.fallback().used_pages(), 0);
+    // the bytes area is only freed as a whole, so it still takes a page
+    assert_eq!(allocator.fallback().available_pages(), 15);
+    allocator.dealloc_pages(early_pages, 4);
+    assert_eq!(allocator.early().used_pages(), 0);
+    assert_eq!(allocator.early().available_pages(), 4);
+
+    // With room in the early arena again, it is preferred.
+    assert!(in_early(allocator.alloc_pages(1, 1).unwrap()));
+}
+
+#[test]
+fn test_owns() {
+    let arena = Arena::new(4);
+    let mut allocator = new_allocator(&arena);
+    assert_eq!(allocator.usable_range(), arena.start()..arena.start() + arena.size());
+
+    let ptr = allocator.alloc(Layout::from_size_align(16, 8).unwrap()).unwrap();
+    assert!(allocator.owns(ptr));
+    let pages = allocator.alloc_pages(1, 1).unwrap();
+    assert!(allocator.owns(NonNull::new(pages as *mut u8).unwrap()));
+
+    let on_stack = 0u8;
+    assert!(!allocator.owns(NonNull::from(&on_stack)));
+    // The end of the arena is not part of it.
+    let end = allocator.usable_range().end;
+    assert!(!allocator.owns(NonNull::new(end as *mut u8).unwrap()));
+}
+
+#[test]
+fn test_alloc_large_align() {
+    let arena = Arena::new(64);
+    let mut allocator = new_allocator(&arena);
+    let range = allocator.usable_range();
+
+    // Misalign the byte cursor first.
+    allocator.alloc(Layout::from_size_align(3, 1).unwrap()).unwrap();
+
+    for align in [PAGE_SIZE, 16 * PAGE_SIZE] {
+        let ptr = allocator.alloc(Layout::from_size_align(10, align).unwrap()).unwrap();
+        let addr = ptr.as_ptr() as usize;
+        assert_eq!(addr % align, 0);
+        assert!(range.contains(&addr) && range.contains(&(addr + 9)));
+    }
+
+    // An alignment far beyond the arena fails without touching the allocator.
+    let available = allocator.available_bytes();
+    let huge = Layout::from_size_align(1, 1 << (usize::BITS - 2)).unwrap();
+    assert_eq!(allocator.alloc(huge), Err(AllocError::NoMemory));
+    assert_eq!(allocator.available_bytes(), available);
+}
+
+#[test]
+fn test_debug() {
+    let arena = Arena::new(16);
+    let mut allocator = new_allocator(&arena);
+    allocator.alloc(Layout::from_size_align(100, 8).unwrap()).unwrap();
+    allocator.alloc_pages(2, 1).unwrap();
+    allocator.alloc(Layout::from_size_align(10, 64).unwrap()).unwrap();
+
+    let dump = format!("{:?}", allocator);
+    let field = |name: &str| {
+        let value = dump.split(&format!("{name}: ")).nth(1).unwrap();
+        let value = value.split([',', ' ']).next().unwrap();
+        match value.strip_prefix("0x") {
+            Some(hex) => usize::from_str_radix(hex, 16).unwrap(),
+            None => value.parse().unwrap(),
+        }
+    };
+    let (start, end) = (field("start"), field("end"));
+    let (b_pos, p_pos) = (field("b_pos"), field("p_pos"));
+    assert!(start <= b_pos && b_pos <= p_pos && p_pos <= end);
+    assert_eq!((start, end), (arena.start(), arena.start() + arena.size()));
+    assert_eq!(p_pos, end - 2 * PAGE_SIZE);
+    assert_eq!(field("used_bytes"), 110);
+    assert_eq!(field("used_pages"), 2);
+    assert_eq!(field("free"), p_pos - b_pos);
+}
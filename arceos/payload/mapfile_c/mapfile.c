@@ -1,9 +1,23 @@
+#define _GNU_SOURCE
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
+#include <errno.h>
 #include <unistd.h>
 #include <fcntl.h>
+#include <dirent.h>
+#include <poll.h>
+#include <signal.h>
 #include <sys/mman.h>
+#include <sys/stat.h>
+#include <sys/sendfile.h>
+#include <sys/shm.h>
+#include <sys/statfs.h>
+#include <sys/syscall.h>
+#include <sys/ioctl.h>
+#include <sys/uio.h>
+#include <sys/wait.h>
+#include <time.h>
 
 void create_file(const char *fname)
 {
@@ -44,6 +58,1838 @@ void verify_file(const char *fname)
     close(fd);
 }
 
+void test_mmap_errno(const char *fname)
+{
+    int fd;
+    char *addr = NULL;
+
+    addr = mmap(NULL, 32, PROT_READ, MAP_PRIVATE, 100, 0);
+    if (addr != MAP_FAILED || errno != EBADF) {
+        printf("Map bad fd error!\n");
+        exit(-1);
+    }
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    addr = mmap(NULL, 1UL << 40, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (addr != MAP_FAILED || errno != ENOMEM) {
+        printf("Map huge region error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test mmap errno ok!\n");
+}
+
+void test_munmap(const char *fname)
+{
+    int fd;
+    char *addr = NULL;
+    char *again = NULL;
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    addr = mmap(NULL, 8192, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (addr == MAP_FAILED) {
+        printf("Map file error!\n");
+        exit(-1);
+    }
+    if (munmap(addr + 1, 4096) != -1 || errno != EINVAL) {
+        printf("Unmap unaligned error!\n");
+        exit(-1);
+    }
+    if (munmap(addr, 8192) != 0) {
+        printf("Unmap file error!\n");
+        exit(-1);
+    }
+    /* The freed range must be reusable by the next mapping. */
+    again = mmap(NULL, 8192, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (again != addr) {
+        printf("Remap file error!\n");
+        exit(-1);
+    }
+    munmap(again, 8192);
+    close(fd);
+    printf("Test munmap ok!\n");
+}
+
+void test_mprotect(const char *fname)
+{
+    int fd;
+    char *addr = NULL;
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    addr = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+    if (addr == MAP_FAILED) {
+        printf("Map file error!\n");
+        exit(-1);
+    }
+    addr[0] = 'H';
+    if (mprotect(addr, 4096, PROT_READ) != 0) {
+        printf("Mprotect error!\n");
+        exit(-1);
+    }
+    /* Reads must still work after dropping the write permission. */
+    if (addr[0] != 'H' || strcmp(addr + 1, "ello, arceos!") != 0) {
+        printf("Read after mprotect error!\n");
+        exit(-1);
+    }
+    /* ... but writes into the page are refused. */
+    if (read(fd, addr, 1) != -1 || errno != EFAULT) {
+        printf("Write after mprotect error!\n");
+        exit(-1);
+    }
+    if (mprotect(addr + 1, 4096, PROT_READ) != -1 || errno != EINVAL) {
+        printf("Mprotect unaligned error!\n");
+        exit(-1);
+    }
+    munmap(addr, 4096);
+    if (mprotect(addr, 4096, PROT_READ) != -1 || errno != ENOMEM) {
+        printf("Mprotect unmapped error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test mprotect ok!\n");
+}
+
+void test_mmap_fixed(const char *fname)
+{
+    int fd;
+    int fd2;
+    char *addr = NULL;
+    char *fixed = NULL;
+    char content[] = "fixed mapping!";
+
+    fd2 = creat("test_fixed", 0600);
+    if (fd2 < 0 || write(fd2, content, strlen(content)+1) < 0) {
+        printf("Write file error!\n");
+        exit(-1);
+    }
+    close(fd2);
+
+    fd = open(fname, O_RDONLY);
+    fd2 = open("test_fixed", O_RDONLY);
+    if (fd < 0 || fd2 < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+
+    addr = mmap(NULL, 8192, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (addr == MAP_FAILED) {
+        printf("Map file error!\n");
+        exit(-1);
+    }
+    fixed = mmap(addr + 4096, 4096, PROT_READ, MAP_PRIVATE | MAP_FIXED, fd2, 0);
+    if (fixed != addr + 4096) {
+        printf("Map fixed error!\n");
+        exit(-1);
+    }
+    if (strcmp(addr, "hello, arceos!") != 0 || strcmp(fixed, content) != 0) {
+        printf("Map fixed content error!\n");
+        exit(-1);
+    }
+    if (mmap(addr + 1, 4096, PROT_READ, MAP_PRIVATE | MAP_FIXED, fd2, 0) != MAP_FAILED
+        || errno != EINVAL) {
+        printf("Map fixed unaligned error!\n");
+        exit(-1);
+    }
+    munmap(addr, 8192);
+    close(fd2);
+    close(fd);
+    printf("Test mmap fixed ok!\n");
+}
+
+void test_lseek(const char *fname)
+{
+    int fd;
+    int ret;
+    char buf[64];
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    if (lseek(fd, 7, SEEK_SET) != 7) {
+        printf("Lseek set error!\n");
+        exit(-1);
+    }
+    ret = read(fd, buf, sizeof(buf));
+    if (ret != 8 || strcmp(buf, "arceos!") != 0) {
+        printf("Read after lseek error!\n");
+        exit(-1);
+    }
+    if (lseek(fd, -8, SEEK_CUR) != 7 || lseek(fd, -1, SEEK_END) != 14) {
+        printf("Lseek cur/end error!\n");
+        exit(-1);
+    }
+    if (lseek(fd, 0, 42) != -1 || errno != EINVAL) {
+        printf("Lseek whence error!\n");
+        exit(-1);
+    }
+    close(fd);
+    if (lseek(fd, 0, SEEK_SET) != -1 || errno != EBADF) {
+        printf("Lseek bad fd error!\n");
+        exit(-1);
+    }
+    printf("Test lseek ok!\n");
+}
+
+void test_fstat(void)
+{
+    int fd;
+    struct stat st;
+    char content[100];
+
+    memset(content, 'x', sizeof(content));
+    fd = open("test_fstat", O_RDWR | O_CREAT, 0600);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    if (write(fd, content, sizeof(content)) != sizeof(content)) {
+        printf("Write file error!\n");
+        exit(-1);
+    }
+    if (fstat(fd, &st) != 0) {
+        printf("Fstat error!\n");
+        exit(-1);
+    }
+    if (st.st_size != sizeof(content) || !S_ISREG(st.st_mode)) {
+        printf("Fstat content error!\n");
+        exit(-1);
+    }
+    close(fd);
+    if (fstat(fd, &st) != -1 || errno != EBADF) {
+        printf("Fstat bad fd error!\n");
+        exit(-1);
+    }
+    printf("Test fstat ok!\n");
+}
+
+void test_brk(void)
+{
+    char *brk_start;
+    char *brk_end;
+
+    /* musl's sbrk() refuses to grow the heap, so use the raw syscall. */
+    brk_start = (char *)syscall(SYS_brk, 0);
+    brk_end = (char *)syscall(SYS_brk, brk_start + 8192);
+    if (brk_end != brk_start + 8192) {
+        printf("Brk grow error!\n");
+        exit(-1);
+    }
+    memset(brk_start, 0x5a, 8192);
+    if (brk_start[0] != 0x5a || brk_start[8191] != 0x5a) {
+        printf("Brk access error!\n");
+        exit(-1);
+    }
+    if ((char *)syscall(SYS_brk, brk_start) != brk_start) {
+        printf("Brk shrink error!\n");
+        exit(-1);
+    }
+    printf("Test brk ok!\n");
+}
+
+void test_getcwd(void)
+{
+    char buf[64];
+
+    if (getcwd(buf, sizeof(buf)) != buf || strcmp(buf, "/") != 0) {
+        printf("Getcwd error!\n");
+        exit(-1);
+    }
+    if (getcwd(buf, 1) != NULL || errno != ERANGE) {
+        printf("Getcwd range error!\n");
+        exit(-1);
+    }
+    printf("Test getcwd ok!\n");
+}
+
+void test_mkdirat(void)
+{
+    int fd;
+
+    if (mkdir("/tmp/test_dir", 0755) != 0 || mkdir("/tmp/test_dir/sub", 0755) != 0) {
+        printf("Mkdir error!\n");
+        exit(-1);
+    }
+    fd = open("/tmp/test_dir/sub/file", O_RDWR | O_CREAT, 0600);
+    if (fd < 0) {
+        printf("Open file in new dir error!\n");
+        exit(-1);
+    }
+    close(fd);
+    if (mkdir("/tmp/test_dir", 0755) != -1 || errno != EEXIST) {
+        printf("Mkdir exist error!\n");
+        exit(-1);
+    }
+    if (mkdir("/tmp/no_dir/sub", 0755) != -1 || errno != ENOENT) {
+        printf("Mkdir no parent error!\n");
+        exit(-1);
+    }
+    printf("Test mkdirat ok!\n");
+}
+
+void test_unlinkat(void)
+{
+    if (unlink("/tmp/test_dir/sub") != -1 || errno != EISDIR) {
+        printf("Unlink dir error!\n");
+        exit(-1);
+    }
+    if (rmdir("/tmp/test_dir/sub") != -1 || errno != ENOTEMPTY) {
+        printf("Rmdir non-empty error!\n");
+        exit(-1);
+    }
+    if (rmdir("/tmp/test_dir/sub/file") != -1 || errno != ENOTDIR) {
+        printf("Rmdir file error!\n");
+        exit(-1);
+    }
+    if (unlink("/tmp/test_dir/sub/file") != 0) {
+        printf("Unlink file error!\n");
+        exit(-1);
+    }
+    if (open("/tmp/test_dir/sub/file", O_RDONLY) != -1 || errno != ENOENT) {
+        printf("Open unlinked file error!\n");
+        exit(-1);
+    }
+    if (rmdir("/tmp/test_dir/sub") != 0) {
+        printf("Rmdir empty error!\n");
+        exit(-1);
+    }
+    printf("Test unlinkat ok!\n");
+}
+
+void check_content(const char *fname, const char *expected)
+{
+    int fd;
+    int ret;
+    char buf[64];
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    ret = read(fd, buf, sizeof(buf) - 1);
+    if (ret < 0) {
+        printf("Read file error!\n");
+        exit(-1);
+    }
+    buf[ret] = 0;
+    if (strcmp(buf, expected) != 0) {
+        printf("Content of '%s' mismatch: %s\n", fname, buf);
+        exit(-1);
+    }
+    close(fd);
+}
+
+void test_renameat2(void)
+{
+    create_file("/tmp/rename_src");
+    create_file("/tmp/rename_other");
+
+    if (rename("/tmp/rename_src", "/tmp/rename_dst") != 0) {
+        printf("Rename error!\n");
+        exit(-1);
+    }
+    check_content("/tmp/rename_dst", "hello, arceos!");
+
+    if (mkdir("/tmp/rename_dir", 0755) != 0
+        || rename("/tmp/rename_dst", "/tmp/rename_dir/moved") != 0) {
+        printf("Rename across dirs error!\n");
+        exit(-1);
+    }
+    check_content("/tmp/rename_dir/moved", "hello, arceos!");
+
+    if (syscall(SYS_renameat2, AT_FDCWD, "/tmp/rename_other",
+                AT_FDCWD, "/tmp/rename_dir/moved", 1) != -1 || errno != EEXIST) {
+        printf("Rename noreplace error!\n");
+        exit(-1);
+    }
+    if (rename("/tmp/rename_src", "/tmp/rename_none") != -1 || errno != ENOENT) {
+        printf("Rename missing error!\n");
+        exit(-1);
+    }
+    printf("Test renameat2 ok!\n");
+}
+
+void test_ioctl(const char *fname)
+{
+    int fd;
+    int on = 1;
+    struct winsize ws;
+
+    memset(&ws, 0, sizeof(ws));
+    if (ioctl(STDOUT_FILENO, TIOCGWINSZ, &ws) != 0 || ws.ws_row == 0 || ws.ws_col == 0) {
+        printf("Ioctl winsize error!\n");
+        exit(-1);
+    }
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    if (ioctl(fd, FIONBIO, &on) != 0) {
+        printf("Ioctl nonblock error!\n");
+        exit(-1);
+    }
+    if (ioctl(fd, TIOCGWINSZ, &ws) != -1 || errno != ENOTTY) {
+        printf("Ioctl winsize on file error!\n");
+        exit(-1);
+    }
+    if (ioctl(fd, 0x1234, &on) != -1 || errno != ENOTTY) {
+        printf("Ioctl unknown op error!\n");
+        exit(-1);
+    }
+    /* Unknown ops are rejected before argp is looked at. */
+    if (ioctl(fd, 0x1234, NULL) != -1 || errno != ENOTTY) {
+        printf("Ioctl unknown op null error!\n");
+        exit(-1);
+    }
+    if (ioctl(fd, FIONBIO, NULL) != -1 || errno != EFAULT) {
+        printf("Ioctl null argp error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test ioctl ok!\n");
+}
+
+void test_readv(void)
+{
+    int fd;
+    char blob[] = "0123456789abcdef";
+    char head[5];
+    char tail[32];
+    struct iovec iov[2];
+
+    fd = open("/tmp/test_readv", O_RDWR | O_CREAT, 0600);
+    if (fd < 0 || write(fd, blob, sizeof(blob)) != sizeof(blob)) {
+        printf("Write file error!\n");
+        exit(-1);
+    }
+    close(fd);
+
+    fd = open("/tmp/test_readv", O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    iov[0].iov_base = head;
+    iov[0].iov_len = sizeof(head);
+    iov[1].iov_base = tail;
+    iov[1].iov_len = sizeof(tail);
+    if (readv(fd, iov, 2) != sizeof(blob)) {
+        printf("Readv error!\n");
+        exit(-1);
+    }
+    if (memcmp(head, "01234", 5) != 0 || strcmp(tail, "56789abcdef") != 0) {
+        printf("Readv content error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test readv ok!\n");
+}
+
+void test_mmap_large(void)
+{
+    int fd;
+    int i;
+    char *addr = NULL;
+    static char page[4096];
+    const int nr_pages = 5;
+    const int len = nr_pages * 4096 + 100;
+
+    fd = open("/tmp/test_large", O_RDWR | O_CREAT, 0600);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    for (i = 0; i < nr_pages; i++) {
+        memset(page, 'a' + i, sizeof(page));
+        if (write(fd, page, sizeof(page)) != sizeof(page)) {
+            printf("Write file error!\n");
+            exit(-1);
+        }
+    }
+    if (write(fd, page, 100) != 100) {
+        printf("Write file error!\n");
+        exit(-1);
+    }
+    close(fd);
+
+    fd = open("/tmp/test_large", O_RDONLY);
+    addr = mmap(NULL, len + 4096, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (addr == MAP_FAILED) {
+        printf("Map large file error!\n");
+        exit(-1);
+    }
+    for (i = 0; i < nr_pages * 4096; i++) {
+        if (addr[i] != 'a' + i / 4096) {
+            printf("Map large content error at %d!\n", i);
+            exit(-1);
+        }
+    }
+    /* The tail of the last file page and the page past EOF are zeroed. */
+    for (i = len; i < len + 4096; i++) {
+        if (addr[i] != 0) {
+            printf("Map large zero-fill error at %d!\n", i);
+            exit(-1);
+        }
+    }
+    munmap(addr, len + 4096);
+    close(fd);
+    printf("Test mmap large ok!\n");
+}
+
+void test_mmap_validate(const char *fname)
+{
+    int fd;
+    char *addr = NULL;
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    if (mmap(NULL, 0, PROT_READ, MAP_PRIVATE, fd, 0) != MAP_FAILED || errno != EINVAL) {
+        printf("Map zero length error!\n");
+        exit(-1);
+    }
+    if (mmap((void *)0x90000123, 4096, PROT_READ, MAP_PRIVATE | MAP_FIXED, fd, 0) != MAP_FAILED
+        || errno != EINVAL) {
+        printf("Map fixed unaligned error!\n");
+        exit(-1);
+    }
+    /* An unaligned hint is only a hint: the result must still be page-aligned. */
+    addr = mmap((void *)0x90000123, 100, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (addr == MAP_FAILED || ((unsigned long)addr & 4095) != 0) {
+        printf("Map unaligned hint error!\n");
+        exit(-1);
+    }
+    if (strcmp(addr, "hello, arceos!") != 0) {
+        printf("Map unaligned hint content error!\n");
+        exit(-1);
+    }
+    munmap(addr, 100);
+    close(fd);
+    printf("Test mmap validate ok!\n");
+}
+
+void test_mmap_oom(const char *fname)
+{
+    int fd;
+    char *addr = NULL;
+
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    /* Far more than the physical memory: population fails part way. */
+    addr = mmap(NULL, 1UL << 30, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (addr != MAP_FAILED || errno != ENOMEM) {
+        printf("Map out of memory error!\n");
+        exit(-1);
+    }
+    /* The partially built mapping must have been released. */
+    addr = mmap(NULL, 4096 * 16, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (addr == MAP_FAILED || strcmp(addr, "hello, arceos!") != 0) {
+        printf("Map after out of memory error!\n");
+        exit(-1);
+    }
+    munmap(addr, 4096 * 16);
+    close(fd);
+    printf("Test mmap oom ok!\n");
+}
+
+void test_dup(void)
+{
+    int fd;
+    int fd2;
+    char buf[16];
+
+    fd = open("/tmp/test_dup", O_RDWR | O_CREAT, 0600);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    fd2 = dup(fd);
+    if (fd2 < 0 || fd2 == fd) {
+        printf("Dup error!\n");
+        exit(-1);
+    }
+    if (write(fd2, "abc", 4) != 4) {
+        printf("Write through dup error!\n");
+        exit(-1);
+    }
+    /* Both descriptors share the same open file and offset. */
+    lseek(fd, 0, SEEK_SET);
+    if (read(fd, buf, sizeof(buf)) != 4 || strcmp(buf, "abc") != 0) {
+        printf("Read through original error!\n");
+        exit(-1);
+    }
+    if (dup3(fd, 100, O_CLOEXEC) != 100) {
+        printf("Dup3 error!\n");
+        exit(-1);
+    }
+    /* dup3 onto an open descriptor closes it first. */
+    if (dup3(fd2, 100, 0) != 100) {
+        printf("Dup3 over open fd error!\n");
+        exit(-1);
+    }
+    if (dup3(fd, fd, 0) != -1 || errno != EINVAL) {
+        printf("Dup3 same fd error!\n");
+        exit(-1);
+    }
+    close(100);
+    close(fd2);
+    close(fd);
+    printf("Test dup ok!\n");
+}
+
+void test_append(void)
+{
+    int fd1;
+    int fd2;
+    char buf[16];
+
+    fd1 = open("/tmp/test_append", O_WRONLY | O_CREAT | O_APPEND, 0600);
+    fd2 = open("/tmp/test_append", O_WRONLY | O_APPEND);
+    if (fd1 < 0 || fd2 < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    /* Each write goes to the current end, whatever the fd offset is. */
+    if (write(fd1, "ab", 2) != 2 || write(fd2, "cd", 2) != 2 ||
+        write(fd1, "ef", 3) != 3) {
+        printf("Append error!\n");
+        exit(-1);
+    }
+    close(fd2);
+    close(fd1);
+    fd1 = open("/tmp/test_append", O_RDONLY);
+    if (read(fd1, buf, sizeof(buf)) != 7 || strcmp(buf, "abcdef") != 0) {
+        printf("Append content error!\n");
+        exit(-1);
+    }
+    close(fd1);
+    printf("Test append ok!\n");
+}
+
+void test_ftruncate(void)
+{
+    int fd;
+    struct stat st;
+    char buf[16];
+
+    fd = open("/tmp/test_ftruncate", O_RDWR | O_CREAT, 0600);
+    if (fd < 0 || write(fd, "hello", 5) != 5) {
+        printf("Create file error!\n");
+        exit(-1);
+    }
+    /* Extending fills the new tail with zeros. */
+    if (ftruncate(fd, 10) != 0 || fstat(fd, &st) != 0 || st.st_size != 10) {
+        printf("Ftruncate up error!\n");
+        exit(-1);
+    }
+    memset(buf, 0xff, sizeof(buf));
+    lseek(fd, 0, SEEK_SET);
+    if (read(fd, buf, sizeof(buf)) != 10 || memcmp(buf, "hello\0\0\0\0\0", 10) != 0) {
+        printf("Ftruncate up content error!\n");
+        exit(-1);
+    }
+    if (ftruncate(fd, 3) != 0 || fstat(fd, &st) != 0 || st.st_size != 3) {
+        printf("Ftruncate down error!\n");
+        exit(-1);
+    }
+    lseek(fd, 0, SEEK_SET);
+    if (read(fd, buf, sizeof(buf)) != 3 || memcmp(buf, "hel", 3) != 0) {
+        printf("Ftruncate down content error!\n");
+        exit(-1);
+    }
+    if (ftruncate(fd, -1) != -1 || errno != EINVAL) {
+        printf("Ftruncate negative length error!\n");
+        exit(-1);
+    }
+    close(fd);
+    if (ftruncate(fd, 0) != -1 || errno != EBADF) {
+        printf("Ftruncate bad fd error!\n");
+        exit(-1);
+    }
+    printf("Test ftruncate ok!\n");
+}
+
+void test_pread_pwrite(void)
+{
+    int fd;
+    char buf[8];
+
+    fd = open("/tmp/test_pread", O_RDWR | O_CREAT, 0600);
+    if (fd < 0 || write(fd, "0123456789", 10) != 10) {
+        printf("Create file error!\n");
+        exit(-1);
+    }
+    lseek(fd, 2, SEEK_SET);
+    memset(buf, 0, sizeof(buf));
+    if (pread(fd, buf, 3, 6) != 3 || memcmp(buf, "678", 3) != 0) {
+        printf("Pread error!\n");
+        exit(-1);
+    }
+    if (pwrite(fd, "ab", 2, 0) != 2) {
+        printf("Pwrite error!\n");
+        exit(-1);
+    }
+    if (pread(fd, buf, 4, 0) != 4 || memcmp(buf, "ab23", 4) != 0) {
+        printf("Pread after pwrite error!\n");
+        exit(-1);
+    }
+    /* The plain read still starts where lseek left it. */
+    if (read(fd, buf, 2) != 2 || memcmp(buf, "23", 2) != 0) {
+        printf("Read position error!\n");
+        exit(-1);
+    }
+    if (pread(fd, buf, 1, -1) != -1 || errno != EINVAL) {
+        printf("Pread negative offset error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test pread/pwrite ok!\n");
+}
+
+static long elapsed_ns(const struct timespec *a, const struct timespec *b)
+{
+    return (b->tv_sec - a->tv_sec) * 1000000000L + (b->tv_nsec - a->tv_nsec);
+}
+
+void test_clock(void)
+{
+    struct timespec t1;
+    struct timespec t2;
+    struct timespec req = { 0, 10000000 }; /* 10ms */
+    volatile int i;
+
+    if (clock_gettime(CLOCK_MONOTONIC, &t1) != 0) {
+        printf("Clock_gettime error!\n");
+        exit(-1);
+    }
+    for (i = 0; i < 100000; i++)
+        ;
+    if (clock_gettime(CLOCK_MONOTONIC, &t2) != 0 || elapsed_ns(&t1, &t2) <= 0) {
+        printf("Clock not advancing error!\n");
+        exit(-1);
+    }
+    if (clock_gettime(-1, &t1) != -1 || errno != EINVAL) {
+        printf("Clock_gettime bad clock error!\n");
+        exit(-1);
+    }
+
+    clock_gettime(CLOCK_MONOTONIC, &t1);
+    if (nanosleep(&req, NULL) != 0) {
+        printf("Nanosleep error!\n");
+        exit(-1);
+    }
+    clock_gettime(CLOCK_MONOTONIC, &t2);
+    if (elapsed_ns(&t1, &t2) < 10000000L) {
+        printf("Nanosleep too short error!\n");
+        exit(-1);
+    }
+    req.tv_nsec = 1000000000L;
+    if (nanosleep(&req, NULL) != -1 || errno != EINVAL) {
+        printf("Nanosleep bad request error!\n");
+        exit(-1);
+    }
+    printf("Test clock ok!\n");
+}
+
+void test_getpid(void)
+{
+    int tid_word;
+    pid_t pid = getpid();
+
+    if (pid <= 0 || getpid() != pid) {
+        printf("Getpid error!\n");
+        exit(-1);
+    }
+    if (syscall(SYS_gettid) != pid || syscall(SYS_set_tid_address, &tid_word) != pid) {
+        printf("Gettid error!\n");
+        exit(-1);
+    }
+    if (getppid() < 0) {
+        printf("Getppid error!\n");
+        exit(-1);
+    }
+    printf("Test getpid ok!\n");
+}
+
+void test_mmap_private(void)
+{
+    const char *fname = "/tmp/test_mmap_private";
+    int fd;
+    char *addr;
+
+    create_file(fname);
+    fd = open(fname, O_RDONLY);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    addr = mmap(NULL, 32, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+    close(fd);
+    if (addr == MAP_FAILED) {
+        printf("Mmap private error!\n");
+        exit(-1);
+    }
+    /* The first write goes to a private copy of the page. */
+    addr[0] = 'H';
+    check_content(fname, "hello, arceos!");
+    if (strcmp(addr, "Hello, arceos!") != 0) {
+        printf("Mmap private content error!\n");
+        exit(-1);
+    }
+    munmap(addr, 32);
+    printf("Test mmap private ok!\n");
+}
+
+void test_mmap_shared(void)
+{
+    const char *fname = "/tmp/test_mmap_shared";
+    int fd, status;
+    char *addr;
+    pid_t pid;
+
+    create_file(fname);
+    fd = open(fname, O_RDWR);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    addr = mmap(NULL, 32, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    close(fd);
+    if (addr == MAP_FAILED) {
+        printf("Mmap shared error!\n");
+        exit(-1);
+    }
+    addr[0] = 'H';
+    if (msync(addr, 32, MS_SYNC) != 0) {
+        printf("Msync error!\n");
+        exit(-1);
+    }
+    check_content(fname, "Hello, arceos!");
+    /* munmap writes back the remaining changes. */
+    addr[7] = 'A';
+    munmap(addr, 32);
+    check_content(fname, "Hello, Arceos!");
+    if (msync(addr, 32, MS_SYNC | MS_ASYNC) != -1 || errno != EINVAL) {
+        printf("Msync bad flags error!\n");
+        exit(-1);
+    }
+
+    /* A forked child writes to the same pages, so the parent sees the change
+     * and writes it back instead of overwriting it with a stale copy. */
+    fd = open(fname, O_RDWR);
+    addr = mmap(NULL, 32, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    close(fd);
+    if (addr == MAP_FAILED) {
+        printf("Mmap shared error!\n");
+        exit(-1);
+    }
+    pid = fork();
+    if (pid == 0) {
+        addr[0] = 'h';
+        _exit(0);
+    }
+    if (pid < 0 || waitpid(pid, &status, 0) != pid || addr[0] != 'h') {
+        printf("Mmap shared fork error!\n");
+        exit(-1);
+    }
+    munmap(addr, 32);
+    check_content(fname, "hello, Arceos!");
+    printf("Test mmap shared ok!\n");
+}
+
+void test_faccessat(void)
+{
+    const char *fname = "/tmp/test_faccessat";
+
+    create_file(fname);
+    if (access(fname, F_OK) != 0 || access(fname, R_OK | W_OK) != 0) {
+        printf("Access existing file error!\n");
+        exit(-1);
+    }
+    if (access("/tmp/no_such_file", F_OK) != -1 || errno != ENOENT) {
+        printf("Access missing file error!\n");
+        exit(-1);
+    }
+    printf("Test faccessat ok!\n");
+}
+
+void test_pipe2(void)
+{
+    int fds[2];
+    char buf[16];
+
+    if (pipe2(fds, O_NONBLOCK) != 0) {
+        printf("Pipe2 error!\n");
+        exit(-1);
+    }
+    if (read(fds[0], buf, sizeof(buf)) != -1 || errno != EAGAIN) {
+        printf("Pipe2 nonblocking read error!\n");
+        exit(-1);
+    }
+    if (write(fds[1], "hello", 5) != 5 ||
+        read(fds[0], buf, sizeof(buf)) != 5 || memcmp(buf, "hello", 5) != 0) {
+        printf("Pipe2 read write error!\n");
+        exit(-1);
+    }
+    close(fds[0]);
+    if (write(fds[1], "hello", 5) != -1 || errno != EPIPE) {
+        printf("Pipe2 broken pipe error!\n");
+        exit(-1);
+    }
+    close(fds[1]);
+    printf("Test pipe2 ok!\n");
+}
+
+void test_efault(void)
+{
+    char *bad;
+    int fd;
+
+    create_file("/tmp/test_efault");
+    fd = open("/tmp/test_efault", O_RDWR);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    /* An address that was mapped once, but not any more. */
+    bad = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+    if (bad == MAP_FAILED || munmap(bad, 4096) != 0) {
+        printf("Mmap error!\n");
+        exit(-1);
+    }
+    if (write(fd, bad, 16) != -1 || errno != EFAULT) {
+        printf("Write bad buffer error!\n");
+        exit(-1);
+    }
+    if (read(fd, bad, 16) != -1 || errno != EFAULT) {
+        printf("Read bad buffer error!\n");
+        exit(-1);
+    }
+    if (syscall(SYS_fstat, fd, bad) != -1 || errno != EFAULT) {
+        printf("Fstat bad buffer error!\n");
+        exit(-1);
+    }
+    close(fd);
+    if (open(bad, O_RDONLY) != -1 || errno != EFAULT) {
+        printf("Open bad path error!\n");
+        exit(-1);
+    }
+    if (syscall(SYS_getcwd, bad, 64) != -1 || errno != EFAULT) {
+        printf("Getcwd bad buffer error!\n");
+        exit(-1);
+    }
+    printf("Test efault ok!\n");
+}
+
+void test_mmap_prot_none(void)
+{
+    const char *fname = "/tmp/test_mmap_prot_none";
+    char *addr, *other;
+    int fd;
+
+    create_file(fname);
+    fd = open(fname, O_RDWR);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    addr = mmap(NULL, 4096, PROT_NONE, MAP_PRIVATE, fd, 0);
+    if (addr == MAP_FAILED) {
+        printf("Mmap prot none error!\n");
+        exit(-1);
+    }
+    /* The range is reserved, so a hint there gets another address. */
+    other = mmap(addr, 4096, PROT_READ, MAP_PRIVATE, fd, 0);
+    if (other == MAP_FAILED || other == addr) {
+        printf("Mmap prot none not reserved!\n");
+        exit(-1);
+    }
+    munmap(other, 4096);
+    /* Neither readable nor writable. */
+    if (write(fd, addr, 1) != -1 || errno != EFAULT) {
+        printf("Mmap prot none readable!\n");
+        exit(-1);
+    }
+    if (read(fd, addr, 1) != -1 || errno != EFAULT) {
+        printf("Mmap prot none writable!\n");
+        exit(-1);
+    }
+    close(fd);
+    munmap(addr, 4096);
+    printf("Test mmap prot none ok!\n");
+}
+
+void test_iovec_validate(void)
+{
+    char buf[16];
+    struct iovec iov[2];
+    int fd;
+
+    fd = open("/tmp/test_iovec_validate", O_RDWR | O_CREAT, 0600);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    iov[0].iov_base = buf;
+    iov[0].iov_len = sizeof(buf);
+    if (writev(fd, iov, -1) != -1 || errno != EINVAL) {
+        printf("Writev negative count error!\n");
+        exit(-1);
+    }
+    if (writev(fd, iov, 1025) != -1 || errno != EINVAL) {
+        printf("Writev oversized count error!\n");
+        exit(-1);
+    }
+    if (readv(fd, iov, -1) != -1 || errno != EINVAL) {
+        printf("Readv negative count error!\n");
+        exit(-1);
+    }
+    if (readv(fd, iov, 1025) != -1 || errno != EINVAL) {
+        printf("Readv oversized count error!\n");
+        exit(-1);
+    }
+    /* The total length overflows ssize_t. */
+    iov[1].iov_base = buf;
+    iov[1].iov_len = (size_t)-1 / 2;
+    if (writev(fd, iov, 2) != -1 || errno != EINVAL) {
+        printf("Writev length overflow error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test iovec validate ok!\n");
+}
+
+static long file_size(int fd)
+{
+    struct stat st;
+
+    if (fstat(fd, &st) != 0) {
+        printf("Fstat error!\n");
+        exit(-1);
+    }
+    return st.st_size;
+}
+
+void test_open_flags(void)
+{
+    const char *existing = "/tmp/test_open_flags";
+    const char *missing = "/tmp/test_open_flags_missing";
+    int fd;
+
+    fd = open(existing, O_WRONLY | O_CREAT | O_TRUNC, 0600);
+    if (fd < 0 || write(fd, "hello", 5) != 5) {
+        printf("Create file error!\n");
+        exit(-1);
+    }
+    close(fd);
+
+    /* Without O_CREAT a missing file is not created. */
+    if (open(missing, O_RDWR) != -1 || errno != ENOENT) {
+        printf("Open missing file error!\n");
+        exit(-1);
+    }
+    if (open(missing, O_RDWR | O_TRUNC) != -1 || errno != ENOENT) {
+        printf("Open missing file with O_TRUNC error!\n");
+        exit(-1);
+    }
+
+    /* O_CREAT opens an existing file as is. */
+    fd = open(existing, O_RDWR | O_CREAT, 0600);
+    if (fd < 0 || file_size(fd) != 5) {
+        printf("O_CREAT existing file error!\n");
+        exit(-1);
+    }
+    close(fd);
+
+    /* O_CREAT | O_EXCL fails if the file exists... */
+    if (open(existing, O_RDWR | O_CREAT | O_EXCL, 0600) != -1 || errno != EEXIST) {
+        printf("O_EXCL existing file error!\n");
+        exit(-1);
+    }
+    /* ...and creates it otherwise. */
+    fd = open(missing, O_RDWR | O_CREAT | O_EXCL, 0600);
+    if (fd < 0 || file_size(fd) != 0) {
+        printf("O_EXCL missing file error!\n");
+        exit(-1);
+    }
+    close(fd);
+    if (open(missing, O_RDWR | O_CREAT | O_EXCL, 0600) != -1 || errno != EEXIST) {
+        printf("O_EXCL created file error!\n");
+        exit(-1);
+    }
+    unlink(missing);
+
+    /* O_CREAT creates a missing file. */
+    fd = open(missing, O_RDWR | O_CREAT, 0600);
+    if (fd < 0 || file_size(fd) != 0) {
+        printf("O_CREAT missing file error!\n");
+        exit(-1);
+    }
+    close(fd);
+    unlink(missing);
+
+    /* O_TRUNC empties an existing file, and O_CREAT | O_TRUNC creates. */
+    fd = open(existing, O_RDWR | O_TRUNC);
+    if (fd < 0 || file_size(fd) != 0) {
+        printf("O_TRUNC existing file error!\n");
+        exit(-1);
+    }
+    close(fd);
+    fd = open(missing, O_RDWR | O_CREAT | O_TRUNC, 0600);
+    if (fd < 0 || file_size(fd) != 0) {
+        printf("O_CREAT | O_TRUNC missing file error!\n");
+        exit(-1);
+    }
+    close(fd);
+    unlink(missing);
+    unlink(existing);
+    printf("Test open flags ok!\n");
+}
+
+void test_statfs(void)
+{
+    const char *fname = "/tmp/test_statfs";
+    static char buf[64 * 1024];
+    struct statfs before, after, st;
+    int fd;
+
+    if (statfs("/tmp", &before) != 0) {
+        printf("Statfs error!\n");
+        exit(-1);
+    }
+    if (before.f_bsize != 4096 || before.f_blocks == 0 || before.f_bfree > before.f_blocks) {
+        printf("Statfs values error!\n");
+        exit(-1);
+    }
+    if (statfs("/tmp/test_statfs_missing", &st) != -1 || errno != ENOENT) {
+        printf("Statfs missing path error!\n");
+        exit(-1);
+    }
+    if (fstatfs(-1, &st) != -1 || errno != EBADF) {
+        printf("Fstatfs bad fd error!\n");
+        exit(-1);
+    }
+
+    fd = open(fname, O_RDWR | O_CREAT | O_TRUNC, 0600);
+    if (fd < 0) {
+        printf("Open file error!\n");
+        exit(-1);
+    }
+    /* libc zeroes the buffer first, so call the kernel directly. */
+    if (syscall(SYS_fstatfs, fd, NULL) != -1 || errno != EFAULT) {
+        printf("Fstatfs bad buffer error!\n");
+        exit(-1);
+    }
+    memset(buf, 'x', sizeof(buf));
+    if (write(fd, buf, sizeof(buf)) != sizeof(buf)) {
+        printf("Write file error!\n");
+        exit(-1);
+    }
+    /* Writing 16 pages takes at least 16 blocks. */
+    if (fstatfs(fd, &after) != 0 || after.f_bfree + 16 > before.f_bfree) {
+        printf("Statfs free blocks error!\n");
+        exit(-1);
+    }
+    close(fd);
+    unlink(fname);
+    printf("Test statfs ok!\n");
+}
+
+void test_mmap_anonymous(void)
+{
+    char *private_map, *shared_map;
+    int i;
+
+    /* Private anonymous pages are zeroed and writable. */
+    private_map = mmap(NULL, 8192, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (private_map == MAP_FAILED) {
+        printf("Mmap private anonymous error!\n");
+        exit(-1);
+    }
+    for (i = 0; i < 8192; i++) {
+        if (private_map[i] != 0) {
+            printf("Mmap private anonymous not zeroed!\n");
+            exit(-1);
+        }
+    }
+    strcpy(private_map + 4096, "private");
+    if (strcmp(private_map + 4096, "private") != 0) {
+        printf("Mmap private anonymous write error!\n");
+        exit(-1);
+    }
+    munmap(private_map, 8192);
+
+    /* Shared anonymous pages are zeroed, and writes are kept after part of
+     * the mapping is unmapped. */
+    shared_map = mmap(NULL, 3 * 4096, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    if (shared_map == MAP_FAILED) {
+        printf("Mmap shared anonymous error!\n");
+        exit(-1);
+    }
+    for (i = 0; i < 3 * 4096; i++) {
+        if (shared_map[i] != 0) {
+            printf("Mmap shared anonymous not zeroed!\n");
+            exit(-1);
+        }
+    }
+    strcpy(shared_map + 4096, "shared");
+    strcpy(shared_map + 2 * 4096, "memory");
+    if (munmap(shared_map + 4096, 4096) != 0) {
+        printf("Munmap shared anonymous error!\n");
+        exit(-1);
+    }
+    if (strcmp(shared_map + 2 * 4096, "memory") != 0) {
+        printf("Mmap shared anonymous data lost!\n");
+        exit(-1);
+    }
+    munmap(shared_map, 4096);
+    munmap(shared_map + 2 * 4096, 4096);
+    printf("Test mmap anonymous ok!\n");
+}
+
+void test_fcntl(void)
+{
+    int fds[2];
+    int fd;
+    char buf[16];
+
+    if (pipe(fds) != 0) {
+        printf("Pipe error!\n");
+        exit(-1);
+    }
+    if ((fcntl(fds[0], F_GETFL) & O_ACCMODE) != O_RDONLY ||
+        (fcntl(fds[1], F_GETFL) & O_ACCMODE) != O_WRONLY ||
+        (fcntl(fds[0], F_GETFL) & O_NONBLOCK) != 0) {
+        printf("Fcntl getfl error!\n");
+        exit(-1);
+    }
+    if (fcntl(fds[0], F_SETFL, O_NONBLOCK) != 0 ||
+        (fcntl(fds[0], F_GETFL) & O_NONBLOCK) == 0) {
+        printf("Fcntl setfl error!\n");
+        exit(-1);
+    }
+    if (read(fds[0], buf, sizeof(buf)) != -1 || errno != EAGAIN) {
+        printf("Fcntl nonblocking read error!\n");
+        exit(-1);
+    }
+    fd = fcntl(fds[1], F_DUPFD, 100);
+    if (fd < 100 || (fcntl(fd, F_GETFL) & O_ACCMODE) != O_WRONLY) {
+        printf("Fcntl dupfd error!\n");
+        exit(-1);
+    }
+    if (write(fd, "hello", 5) != 5 ||
+        read(fds[0], buf, sizeof(buf)) != 5 || memcmp(buf, "hello", 5) != 0) {
+        printf("Fcntl dupfd read write error!\n");
+        exit(-1);
+    }
+    if (fcntl(fd, 0x7fff) != -1 || errno != EINVAL) {
+        printf("Fcntl bad cmd error!\n");
+        exit(-1);
+    }
+    close(fd);
+    close(fds[0]);
+    close(fds[1]);
+    printf("Test fcntl ok!\n");
+}
+
+void test_sendfile(void)
+{
+    static char data[10000];
+    static char copy[10000];
+    int in, out;
+    off_t off;
+    int i;
+
+    for (i = 0; i < sizeof(data); i++) {
+        data[i] = 'a' + i % 26;
+    }
+    in = open("/tmp/test_sendfile_in", O_RDWR | O_CREAT | O_TRUNC, 0600);
+    out = open("/tmp/test_sendfile_out", O_RDWR | O_CREAT | O_TRUNC, 0600);
+    if (in < 0 || out < 0 || write(in, data, sizeof(data)) != sizeof(data)) {
+        printf("Sendfile create error!\n");
+        exit(-1);
+    }
+
+    /* Without an offset, the copy starts at and advances the file position. */
+    lseek(in, 0, SEEK_SET);
+    if (sendfile(out, in, NULL, 2 * sizeof(data)) != sizeof(data) ||
+        lseek(in, 0, SEEK_CUR) != sizeof(data)) {
+        printf("Sendfile copy error!\n");
+        exit(-1);
+    }
+    if (pread(out, copy, sizeof(copy), 0) != sizeof(copy) ||
+        memcmp(copy, data, sizeof(data)) != 0) {
+        printf("Sendfile content error!\n");
+        exit(-1);
+    }
+
+    /* With an offset, it is updated instead of the file position. */
+    off = 100;
+    if (sendfile(out, in, &off, 50) != 50 || off != 150 ||
+        lseek(in, 0, SEEK_CUR) != sizeof(data)) {
+        printf("Sendfile offset error!\n");
+        exit(-1);
+    }
+    if (pread(out, copy, 50, sizeof(data)) != 50 || memcmp(copy, data + 100, 50) != 0) {
+        printf("Sendfile offset content error!\n");
+        exit(-1);
+    }
+    close(in);
+    close(out);
+    printf("Test sendfile ok!\n");
+}
+
+void test_chdir(void)
+{
+    char cwd[64];
+    char buf[16];
+    int dirfd, fd;
+
+    if (mkdir("/tmp/test_chdir", 0755) != 0) {
+        printf("Mkdir error!\n");
+        exit(-1);
+    }
+    create_file("/tmp/test_chdir/f");
+    dirfd = open("/tmp/test_chdir", O_RDONLY | O_DIRECTORY);
+    if (dirfd < 0) {
+        printf("Open dir error!\n");
+        exit(-1);
+    }
+
+    /* Relative paths resolve against the new cwd. */
+    if (chdir("/tmp/test_chdir") != 0 || getcwd(cwd, sizeof(cwd)) == NULL ||
+        strcmp(cwd, "/tmp/test_chdir") != 0) {
+        printf("Chdir error!\n");
+        exit(-1);
+    }
+    fd = open("f", O_RDONLY);
+    if (fd < 0 || read(fd, buf, sizeof(buf)) != 15 || strcmp(buf, "hello, arceos!") != 0) {
+        printf("Chdir relative open error!\n");
+        exit(-1);
+    }
+    if (chdir("f") != -1 || errno != ENOTDIR) {
+        printf("Chdir to file error!\n");
+        exit(-1);
+    }
+    if (chdir("missing") != -1 || errno != ENOENT) {
+        printf("Chdir to missing error!\n");
+        exit(-1);
+    }
+    if (chdir("..") != 0 || getcwd(cwd, sizeof(cwd)) == NULL || strcmp(cwd, "/tmp") != 0) {
+        printf("Chdir to parent error!\n");
+        exit(-1);
+    }
+
+    if (fchdir(dirfd) != 0 || getcwd(cwd, sizeof(cwd)) == NULL ||
+        strcmp(cwd, "/tmp/test_chdir") != 0) {
+        printf("Fchdir error!\n");
+        exit(-1);
+    }
+    if (fchdir(fd) != -1 || errno != ENOTDIR) {
+        printf("Fchdir to file error!\n");
+        exit(-1);
+    }
+    close(fd);
+    close(dirfd);
+    chdir("/");
+    printf("Test chdir ok!\n");
+}
+
+void test_ppoll(void)
+{
+    struct pollfd pfds[3];
+    struct timespec ts = { 0, 20 * 1000 * 1000 }, start, end;
+    int fds[2], status;
+    char buf[8];
+    pid_t pid;
+
+    if (pipe(fds) != 0) {
+        printf("Pipe error!\n");
+        exit(-1);
+    }
+    pfds[0].fd = fds[0];
+    pfds[0].events = POLLIN;
+    pfds[1].fd = -1;
+    pfds[1].events = POLLIN;
+    pfds[2].fd = 1000;
+    pfds[2].events = POLLIN;
+
+    /* Nothing to read: the call waits for the whole timeout, and only the
+     * invalid fd is reported. */
+    clock_gettime(CLOCK_MONOTONIC, &start);
+    if (ppoll(pfds, 1, &ts, NULL) != 0 || pfds[0].revents != 0) {
+        printf("Ppoll timeout error!\n");
+        exit(-1);
+    }
+    clock_gettime(CLOCK_MONOTONIC, &end);
+    if (elapsed_ns(&start, &end) < ts.tv_nsec) {
+        printf("Ppoll returned early!\n");
+        exit(-1);
+    }
+    if (ppoll(pfds, 3, &ts, NULL) != 1 || pfds[0].revents != 0 ||
+        pfds[1].revents != 0 || pfds[2].revents != POLLNVAL) {
+        printf("Ppoll invalid fd error!\n");
+        exit(-1);
+    }
+
+    /* The pipe becomes readable once something is written. */
+    if (write(fds[1], "ping", 4) != 4) {
+        printf("Pipe write error!\n");
+        exit(-1);
+    }
+    pfds[1].fd = fds[1];
+    pfds[1].events = POLLOUT;
+    if (ppoll(pfds, 2, NULL, NULL) != 2 || pfds[0].revents != POLLIN ||
+        pfds[1].revents != POLLOUT) {
+        printf("Ppoll ready error!\n");
+        exit(-1);
+    }
+    if (read(fds[0], buf, sizeof(buf)) != 4 || ppoll(pfds, 1, &ts, NULL) != 0) {
+        printf("Ppoll drained error!\n");
+        exit(-1);
+    }
+
+    /* A blocked call wakes up when another task writes to the pipe. */
+    pid = fork();
+    if (pid == 0) {
+        nanosleep(&ts, NULL);
+        _exit(write(fds[1], "pong", 4) == 4 ? 0 : 1);
+    }
+    if (pid < 0 || ppoll(pfds, 1, NULL, NULL) != 1 || pfds[0].revents != POLLIN ||
+        read(fds[0], buf, sizeof(buf)) != 4 || memcmp(buf, "pong", 4) != 0) {
+        printf("Ppoll wakeup error!\n");
+        exit(-1);
+    }
+    if (waitpid(pid, &status, 0) != pid || WEXITSTATUS(status) != 0) {
+        printf("Ppoll writer error!\n");
+        exit(-1);
+    }
+    close(fds[0]);
+    close(fds[1]);
+    printf("Test ppoll ok!\n");
+}
+
+struct linux_dirent64 {
+    unsigned long long d_ino;
+    long long d_off;
+    unsigned short d_reclen;
+    unsigned char d_type;
+    char d_name[];
+};
+
+void test_getdents64(void)
+{
+    /* Small enough that the listing takes several calls. */
+    char buf[64];
+    char path[64];
+    int seen[5] = { 0 };
+    int fd, i, n, pos, calls = 0, entries = 0;
+
+    if (mkdir("/tmp/test_getdents", 0755) != 0) {
+        printf("Mkdir error!\n");
+        exit(-1);
+    }
+    for (i = 0; i < 5; i++) {
+        sprintf(path, "/tmp/test_getdents/file%d", i);
+        create_file(path);
+    }
+    fd = open("/tmp/test_getdents", O_RDONLY | O_DIRECTORY);
+    if (fd < 0) {
+        printf("Open dir error!\n");
+        exit(-1);
+    }
+    if (syscall(SYS_getdents64, fd, buf, 8) != -1 || errno != EINVAL) {
+        printf("Getdents64 small buffer error!\n");
+        exit(-1);
+    }
+    while ((n = syscall(SYS_getdents64, fd, buf, sizeof(buf))) > 0) {
+        calls++;
+        for (pos = 0; pos < n; ) {
+            struct linux_dirent64 *d = (struct linux_dirent64 *)(buf + pos);
+            entries++;
+            if (strncmp(d->d_name, "file", 4) == 0) {
+                if (d->d_type != DT_REG) {
+                    printf("Getdents64 type error!\n");
+                    exit(-1);
+                }
+                seen[d->d_name[4] - '0']++;
+            } else if (d->d_type != DT_DIR) {
+                printf("Getdents64 dir type error!\n");
+                exit(-1);
+            }
+            pos += d->d_reclen;
+        }
+    }
+    if (n != 0 || calls < 2 || entries != 7) {
+        printf("Getdents64 listing error!\n");
+        exit(-1);
+    }
+    for (i = 0; i < 5; i++) {
+        if (seen[i] != 1) {
+            printf("Getdents64 entry error!\n");
+            exit(-1);
+        }
+    }
+    close(fd);
+    printf("Test getdents64 ok!\n");
+}
+
+void test_symlink(void)
+{
+    char buf[32];
+    int fd, n;
+
+    if (mkdir("/tmp/test_symlink", 0755) != 0) {
+        printf("Mkdir error!\n");
+        exit(-1);
+    }
+    create_file("/tmp/test_symlink/target");
+    if (symlink("target", "/tmp/test_symlink/link") != 0 ||
+        symlink("/tmp/test_symlink", "/tmp/test_symlink/dir") != 0) {
+        printf("Symlink error!\n");
+        exit(-1);
+    }
+    if (symlink("target", "/tmp/test_symlink/link") != -1 || errno != EEXIST) {
+        printf("Symlink exist error!\n");
+        exit(-1);
+    }
+
+    /* The target comes back verbatim, without a trailing NUL. */
+    memset(buf, 'x', sizeof(buf));
+    n = readlink("/tmp/test_symlink/link", buf, sizeof(buf));
+    if (n != 6 || memcmp(buf, "target", 6) != 0 || buf[6] != 'x') {
+        printf("Readlink error!\n");
+        exit(-1);
+    }
+    if (readlink("/tmp/test_symlink/link", buf, 3) != 3 || memcmp(buf, "tar", 3) != 0) {
+        printf("Readlink truncate error!\n");
+        exit(-1);
+    }
+    if (readlink("/tmp/test_symlink/target", buf, sizeof(buf)) != -1 || errno != EINVAL) {
+        printf("Readlink regular file error!\n");
+        exit(-1);
+    }
+
+    /* Opening goes through the links, including one in the middle. */
+    fd = open("/tmp/test_symlink/dir/link", O_RDONLY);
+    if (fd < 0) {
+        printf("Open through symlink error!\n");
+        exit(-1);
+    }
+    n = read(fd, buf, sizeof(buf));
+    if (n != 15 || strcmp(buf, "hello, arceos!") != 0) {
+        printf("Read through symlink error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test symlink ok!\n");
+}
+
+void test_wait4(void)
+{
+    int status = 0;
+    pid_t parent = getpid(), pid;
+
+    /* Without children there is never anyone to wait for. */
+    if (waitpid(-1, &status, WNOHANG) != -1 || errno != ECHILD) {
+        printf("Wait4 nohang error!\n");
+        exit(-1);
+    }
+    if (waitpid(-1, &status, 0) != -1 || errno != ECHILD) {
+        printf("Wait4 error!\n");
+        exit(-1);
+    }
+    if (waitpid(getpid() + 1, NULL, 0) != -1 || errno != ECHILD) {
+        printf("Wait4 pid error!\n");
+        exit(-1);
+    }
+    if (waitpid(-1, &status, 0x100000) != -1 || errno != EINVAL) {
+        printf("Wait4 options error!\n");
+        exit(-1);
+    }
+
+    /* The exit code of a forked child reaches the parent. */
+    pid = fork();
+    if (pid < 0) {
+        printf("Fork error!\n");
+        exit(-1);
+    }
+    if (pid == 0) {
+        _exit(getppid() == parent ? 42 : 1);
+    }
+    if (waitpid(pid, &status, 0) != pid) {
+        printf("Wait4 child error!\n");
+        exit(-1);
+    }
+    if (!WIFEXITED(status) || WEXITSTATUS(status) != 42) {
+        printf("Wait4 status error!\n");
+        exit(-1);
+    }
+    /* The child is reaped only once. */
+    if (waitpid(pid, &status, WNOHANG) != -1 || errno != ECHILD) {
+        printf("Wait4 reaped error!\n");
+        exit(-1);
+    }
+    printf("Test wait4 ok!\n");
+}
+
+void test_signal_stubs(void)
+{
+    struct { void *next; long offset; void *pending; } head = { &head, 0, NULL };
+    sigset_t set, old, pending;
+    unsigned long raw;
+
+    /* The calls a libc runtime makes at startup succeed. */
+    if (syscall(SYS_set_robust_list, &head, sizeof(head)) != 0) {
+        printf("Set robust list error!\n");
+        exit(-1);
+    }
+    if (syscall(SYS_set_robust_list, &head, 1) != -1 || errno != EINVAL) {
+        printf("Set robust list len error!\n");
+        exit(-1);
+    }
+
+    /* The blocked mask is kept, except for SIGKILL and SIGSTOP. */
+    sigemptyset(&set);
+    sigaddset(&set, SIGUSR1);
+    sigaddset(&set, SIGKILL);
+    if (sigprocmask(SIG_BLOCK, &set, &old) != 0 || sigismember(&old, SIGUSR1)) {
+        printf("Sigprocmask block error!\n");
+        exit(-1);
+    }
+    if (sigprocmask(SIG_SETMASK, NULL, &old) != 0 ||
+        !sigismember(&old, SIGUSR1) || sigismember(&old, SIGKILL)) {
+        printf("Sigprocmask mask error!\n");
+        exit(-1);
+    }
+    if (sigpending(&pending) != 0 || sigismember(&pending, SIGUSR1)) {
+        printf("Sigpending error!\n");
+        exit(-1);
+    }
+    if (sigprocmask(SIG_UNBLOCK, &set, &old) != 0 || sigprocmask(SIG_SETMASK, NULL, &old) != 0 ||
+        sigismember(&old, SIGUSR1)) {
+        printf("Sigprocmask unblock error!\n");
+        exit(-1);
+    }
+
+    /* An unknown `how` or a wrong set size is still rejected. */
+    raw = 0;
+    if (syscall(SYS_rt_sigprocmask, 5, &raw, NULL, 8) != -1 || errno != EINVAL) {
+        printf("Sigprocmask how error!\n");
+        exit(-1);
+    }
+    if (syscall(SYS_rt_sigprocmask, SIG_BLOCK, &raw, NULL, 4) != -1 || errno != EINVAL) {
+        printf("Sigprocmask size error!\n");
+        exit(-1);
+    }
+    printf("Test signal stubs ok!\n");
+}
+
+void test_mmap_offset(void)
+{
+    static char page[4096];
+    char buf[16];
+    char *addr;
+    int fd;
+
+    fd = open("/tmp/test_mmap_offset", O_RDWR | O_CREAT, 0600);
+    memset(page, 'a', sizeof(page));
+    if (fd < 0 || write(fd, page, sizeof(page)) != sizeof(page) ||
+        write(fd, "second page", 12) != 12) {
+        printf("Create file error!\n");
+        exit(-1);
+    }
+    /* Leave the cursor somewhere else than the mapped offset. */
+    lseek(fd, 3, SEEK_SET);
+
+    if (mmap(NULL, 4096, PROT_READ, MAP_PRIVATE, fd, 100) != MAP_FAILED || errno != EINVAL) {
+        printf("Mmap unaligned offset error!\n");
+        exit(-1);
+    }
+    addr = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 4096);
+    if (addr == MAP_FAILED || strcmp(addr, "second page") != 0) {
+        printf("Mmap offset read error!\n");
+        exit(-1);
+    }
+    /* Loading the pages did not move the cursor. */
+    if (read(fd, buf, 2) != 2 || memcmp(buf, "aa", 2) != 0 || lseek(fd, 0, SEEK_CUR) != 5) {
+        printf("Mmap offset cursor error!\n");
+        exit(-1);
+    }
+    /* Changes are written back at the mapped offset, not at the start. */
+    memcpy(addr, "SECOND", 6);
+    munmap(addr, 4096);
+    if (pread(fd, buf, 12, 4096) != 12 || strcmp(buf, "SECOND page") != 0) {
+        printf("Mmap offset write back error!\n");
+        exit(-1);
+    }
+    if (pread(fd, buf, 6, 0) != 6 || memcmp(buf, "aaaaaa", 6) != 0) {
+        printf("Mmap offset overwrite error!\n");
+        exit(-1);
+    }
+    close(fd);
+    printf("Test mmap offset ok!\n");
+}
+
+void test_mprotect_guard(void)
+{
+    char buf[8];
+    char *addr;
+    int fds[2];
+
+    /* Reserve two pages, then open up the first one; the second stays a guard. */
+    addr = mmap(NULL, 4096 * 2, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (addr == MAP_FAILED) {
+        printf("Mmap guard error!\n");
+        exit(-1);
+    }
+    if (mprotect(addr, 4096, PROT_READ | PROT_WRITE) != 0) {
+        printf("Mprotect guard error!\n");
+        exit(-1);
+    }
+    strcpy(addr, "guard");
+    if (strcmp(addr, "guard") != 0) {
+        printf("Access after mprotect error!\n");
+        exit(-1);
+    }
+    if (pipe(fds) != 0 || write(fds[1], "x", 1) != 1) {
+        printf("Pipe error!\n");
+        exit(-1);
+    }
+    if (read(fds[0], addr + 4096, 1) != -1 || errno != EFAULT) {
+        printf("Guard page accessible error!\n");
+        exit(-1);
+    }
+    close(fds[0]);
+    close(fds[1]);
+    munmap(addr, 4096 * 2);
+
+    /* Lazily allocated pages count as mapped before they are touched. */
+    addr = mmap(NULL, 4096 * 2, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (addr == MAP_FAILED || msync(addr, 4096 * 2, MS_SYNC) != 0 ||
+        mprotect(addr + 4096, 4096, PROT_READ) != 0) {
+        printf("Mprotect lazy mapping error!\n");
+        exit(-1);
+    }
+    memcpy(buf, addr + 4096, sizeof(buf));
+    if (buf[0] != 0) {
+        printf("Read lazy mapping error!\n");
+        exit(-1);
+    }
+    munmap(addr, 4096 * 2);
+    printf("Test mprotect guard ok!\n");
+}
+
+void test_shm(void)
+{
+    char *shared_map, *private_map, *seg;
+    int status, id;
+    pid_t pid;
+
+    /* Shared anonymous pages stay shared with a forked child, private ones
+     * are copied. */
+    shared_map = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_SHARED | MAP_ANONYMOUS, -1, 0);
+    private_map = mmap(NULL, 4096, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    if (shared_map == MAP_FAILED || private_map == MAP_FAILED) {
+        printf("Shm mmap error!\n");
+        exit(-1);
+    }
+    strcpy(private_map, "parent");
+    pid = fork();
+    if (pid == 0) {
+        status = strcmp(private_map, "parent") == 0 ? 0 : 1;
+        strcpy(shared_map, "from child");
+        strcpy(private_map, "child");
+        _exit(status);
+    }
+    if (pid < 0 || waitpid(pid, &status, 0) != pid || WEXITSTATUS(status) != 0) {
+        printf("Shm fork error!\n");
+        exit(-1);
+    }
+    if (strcmp(shared_map, "from child") != 0) {
+        printf("Shm shared anonymous error!\n");
+        exit(-1);
+    }
+    if (strcmp(private_map, "parent") != 0) {
+        printf("Shm private anonymous error!\n");
+        exit(-1);
+    }
+    munmap(shared_map, 4096);
+    munmap(private_map, 4096);
+
+    /* A keyed segment is found again by its key. */
+    id = shmget(0x1234, 8192, IPC_CREAT | 0600);
+    if (id < 0 || shmget(0x1234, 4096, 0) != id) {
+        printf("Shmget key error!\n");
+        exit(-1);
+    }
+    if (shmget(0x1234, 4096, IPC_CREAT | IPC_EXCL | 0600) != -1 || errno != EEXIST) {
+        printf("Shmget excl error!\n");
+        exit(-1);
+    }
+    if (shmget(0x4321, 4096, 0) != -1 || errno != ENOENT) {
+        printf("Shmget noent error!\n");
+        exit(-1);
+    }
+
+    /* The child attaches the segment in its own address space and writes,
+     * the parent attaches it afterwards and reads the value. */
+    pid = fork();
+    if (pid == 0) {
+        seg = shmat(shmget(0x1234, 0, 0), NULL, 0);
+        if (seg == (void *)-1) {
+            _exit(1);
+        }
+        strcpy(seg + 4096, "keyed");
+        _exit(shmdt(seg) == 0 ? 0 : 1);
+    }
+    if (pid < 0 || waitpid(pid, &status, 0) != pid || WEXITSTATUS(status) != 0) {
+        printf("Shm child error!\n");
+        exit(-1);
+    }
+    seg = shmat(id, NULL, SHM_RDONLY);
+    if (seg == (void *)-1 || strcmp(seg + 4096, "keyed") != 0) {
+        printf("Shmat error!\n");
+        exit(-1);
+    }
+
+    /* Removed segments are not found, but stay mapped until detached. */
+    if (shmctl(id, IPC_RMID, NULL) != 0 || shmget(0x1234, 4096, 0) != -1) {
+        printf("Shmctl rmid error!\n");
+        exit(-1);
+    }
+    if (strcmp(seg + 4096, "keyed") != 0) {
+        printf("Shm removed segment error!\n");
+        exit(-1);
+    }
+    if (shmdt(seg) != 0 || shmdt(seg) != -1 || errno != EINVAL) {
+        printf("Shmdt error!\n");
+        exit(-1);
+    }
+    printf("Test shm ok!\n");
+}
+
 int main()
 {
     int fd;
@@ -54,6 +1900,49 @@ int main()
 
     create_file(fname);
     verify_file(fname);
+    test_mmap_errno(fname);
+    test_munmap(fname);
+    test_mprotect(fname);
+    test_mmap_fixed(fname);
+    test_lseek(fname);
+    test_fstat();
+    test_brk();
+    test_getcwd();
+    test_mkdirat();
+    test_unlinkat();
+    test_renameat2();
+    test_ioctl(fname);
+    test_readv();
+    test_mmap_large();
+    test_mmap_validate(fname);
+    test_mmap_oom(fname);
+    test_dup();
+    test_append();
+    test_ftruncate();
+    test_pread_pwrite();
+    test_clock();
+    test_getpid();
+    test_mmap_private();
+    test_mmap_shared();
+    test_faccessat();
+    test_pipe2();
+    test_efault();
+    test_mmap_prot_none();
+    test_iovec_validate();
+    test_open_flags();
+    test_statfs();
+    test_mmap_anonymous();
+    test_fcntl();
+    test_sendfile();
+    test_chdir();
+    test_ppoll();
+    test_getdents64();
+    test_symlink();
+    test_wait4();
+    test_signal_stubs();
+    test_mmap_offset();
+    test_mprotect_guard();
+    test_shm();
 
     printf("MapFile ok!\n");
     return 0;
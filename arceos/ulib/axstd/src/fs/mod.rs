@@ -75,3 +75,16 @@ pub fn remove_file(path: &str) -> io::Result<()> {
 pub fn rename(old: &str, new: &str) -> io::Result<()> {
     arceos_api::fs::ax_rename(old, new)
 }
+
+/// Creates a new symbolic link at `path` which points to `target`.
+///
+/// The target is not required to exist.
+pub fn symlink(target: &str, path: &str) -> io::Result<()> {
+    arceos_api::fs::ax_symlink(target, path)
+}
+
+/// Reads a symbolic link, returning the path it points to.
+#[cfg(feature = "alloc")]
+pub fn read_link(path: &str) -> io::Result<String> {
+    arceos_api::fs::ax_read_link(path)
+}
@@ -1,6 +1,10 @@
+use core::alloc::Layout;
 use core::borrow::Borrow;
 use core::hash::{BuildHasher, Hash, Hasher};
+use core::iter::FusedIterator;
 use core::mem;
+use core::ops::Index;
+use core::sync::atomic::{AtomicU64, Ordering};
 
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
@@ -13,21 +17,81 @@ use arceos_api::sys::ax_rand_u64;
 
 // 默认初始容量，最好是2的幂
 const INITIAL_CAPACITY: usize = 8;
-// 默认负载因子阈值
+// 默认负载因子阈值，可以通过 HashMap::with_load_factor 修改
 const LOAD_FACTOR_THRESHOLD: f32 = 0.75;
+// 随机源返回无效种子时最多重试的次数
+const SEED_RETRIES: usize = 4;
+
+// 上一次得到的种子，用于发现随机源重复返回同一个值
+static LAST_SEED: AtomicU64 = AtomicU64::new(0);
+// 随机源不可用时混入种子的计数器，保证每个实例都不同
+static SEED_COUNTER: AtomicU64 = AtomicU64::new(1);
 
 // --- Hasher 和 BuildHasher 实现 ---
 
 /// 自定义的简单哈希状态构建器，使用 axhal 的随机数
-#[derive(Clone, Default)]
-pub struct AxRandomState;
+///
+/// 种子在构造时确定，之后同一个实例构建出的哈希器行为完全一致。
+#[derive(Clone)]
+pub struct AxRandomState {
+    seed: u64,
+}
 
 impl AxRandomState {
+    /// 使用随机种子创建，种子来自 `ax_rand_u64`。
     pub fn new() -> Self {
-        AxRandomState
+        Self::with_rng(&mut ax_rand_u64)
+    }
+
+    /// 从给定的随机源取种子，便于在测试中替换系统随机源，或者使用其他熵源。
+    ///
+    /// 随机源返回 0（例如启动早期尚未播种）或与上一次相同的值时会重试几次，
+    /// 仍然失败则混入一个全局计数器，保证不同实例的种子互不相同。
+    pub fn with_rng<R: RandomSource + ?Sized>(rng: &mut R) -> Self {
+        for _ in 0..SEED_RETRIES {
+            let seed = rng.next_u64();
+            if seed != 0 && LAST_SEED.swap(seed, Ordering::Relaxed) != seed {
+                return AxRandomState { seed };
+            }
+        }
+        let count = SEED_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let seed = mix64(rng.next_u64() ^ count.wrapping_mul(0x9e3779b97f4a7c15_u64));
+        AxRandomState { seed }
+    }
+
+    /// 使用固定的种子创建，用于需要可复现桶布局的场景（例如测试）。
+    pub fn with_seed(seed: u64) -> Self {
+        AxRandomState { seed }
     }
 }
 
+/// `AxRandomState` 的种子来源。
+///
+/// 所有 `FnMut() -> u64` 都实现了它，因此可以直接传入函数或闭包。
+pub trait RandomSource {
+    /// 返回下一个随机数。
+    fn next_u64(&mut self) -> u64;
+}
+
+impl<F: FnMut() -> u64> RandomSource for F {
+    fn next_u64(&mut self) -> u64 {
+        self()
+    }
+}
+
+impl Default for AxRandomState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 使用固定种子的哈希状态构建器（FNV 风格）。
+///
+/// 不能抵御哈希洪水攻击，但速度快、桶布局在每次运行中都相同，
+/// 适合内部使用、需要确定性的表。
+#[derive(Clone, Copy, Default)]
+pub struct FnvBuildHasher;
+
 /// 一个非常基础的哈希器实现
 pub struct SimpleHasher {
     state: u64,
@@ -47,13 +111,7 @@ impl SimpleHasher {
 impl Hasher for SimpleHasher {
     fn finish(&self) -> u64 {
         // 可以添加一个最终的混淆步骤
-        let mut x = self.state;
-        x ^= x >> 30;
-        x = x.wrapping_mul(0xbf58476d1ce4e5b9_u64);
-        x ^= x >> 27;
-        x = x.wrapping_mul(0x94d049bb133111eb_u64);
-        x ^= x >> 31;
-        x
+        mix64(self.state)
     }
 
     fn write(&mut self, bytes: &[u8]) {
@@ -64,12 +122,52 @@ impl Hasher for SimpleHasher {
     }
 }
 
+/// [`HashMap::try_reserve`] 失败的原因。
+///
+/// 全局分配器的 `GlobalAlloc` 接口在内存不足时直接中止，`Vec::try_reserve` 无法报告
+/// 分配失败，所以 HashMap 自己向分配器申请桶数组，并使用自己的错误类型。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// 所需的桶数或字节数超出了 `usize` / `isize::MAX` 的范围。
+    CapacityOverflow,
+    /// 分配器无法提供 `layout` 大小的内存。
+    AllocError {
+        /// 申请失败的内存布局。
+        layout: Layout,
+    },
+}
+
+/// 哈希值 `hash` 在 `num_buckets` 个桶（2 的幂）中的下标。
+fn bucket_index_in(hash: u64, num_buckets: usize) -> usize {
+    // 掩码只取低位，先混淆一次，让高位不同的键也能分散到不同的桶
+    // （哈希器不一定是 SimpleHasher，不能假设它已经混淆过）
+    // 确保桶的数量是2的幂，这样可以用位运算代替取模
+    (mix64(hash) & (num_buckets as u64 - 1)) as usize
+}
+
+/// 最终的雪崩混淆，让每一位输入都影响到所有输出位
+fn mix64(mut x: u64) -> u64 {
+    x ^= x >> 30;
+    x = x.wrapping_mul(0xbf58476d1ce4e5b9_u64);
+    x ^= x >> 27;
+    x = x.wrapping_mul(0x94d049bb133111eb_u64);
+    x ^= x >> 31;
+    x
+}
+
 impl BuildHasher for AxRandomState {
     type Hasher = SimpleHasher;
 
     fn build_hasher(&self) -> Self::Hasher {
-        // 确认这里调用的是你新导出的函数
-        SimpleHasher::new(ax_rand_u64()) // ax_rand_u64() 现在应该能被解析
+        SimpleHasher::new(self.seed)
+    }
+}
+
+impl BuildHasher for FnvBuildHasher {
+    type Hasher = SimpleHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        SimpleHasher::new(0)
     }
 }
 
@@ -85,10 +183,23 @@ impl<K, V> Bucket<K, V> {
     }
 }
 
+impl<K: Clone, V: Clone> Clone for Bucket<K, V> {
+    fn clone(&self) -> Self {
+        Bucket { items: self.items.clone() }
+    }
+
+    // Vec::clone_from 会复用已有的空间
+    fn clone_from(&mut self, source: &Self) {
+        self.items.clone_from(&source.items);
+    }
+}
+
 pub struct HashMap<K, V, S = AxRandomState> {
     buckets: Vec<Bucket<K, V>>,
     len: usize,
     hasher_builder: S,
+    load_factor_threshold: f32, // 负载因子阈值，超过后扩容
+    resizes: usize,             // 扩容的次数，用于统计
 }
 
 impl<K, V> HashMap<K, V, AxRandomState>
@@ -101,6 +212,30 @@ where
     pub fn new() -> Self {
         Self::with_capacity_and_hasher(INITIAL_CAPACITY, AxRandomState::new())
     }
+
+    /// 创建一个使用指定负载因子阈值的空 HashMap。
+    /// 阈值越低，桶越多、冲突越少；越高则越省内存。
+    ///
+    /// `lf` 必须在 `(0.0, 1.0)` 之间，否则 panic。
+    #[cfg(feature = "alloc")]
+    pub fn with_load_factor(lf: f32) -> Self {
+        assert!(lf > 0.0 && lf < 1.0, "load factor must be in (0.0, 1.0)");
+        let mut map = Self::new();
+        map.load_factor_threshold = lf;
+        map
+    }
+}
+
+impl<K, V> HashMap<K, V, FnvBuildHasher>
+where
+    K: Hash + Eq,
+{
+    /// 创建一个使用 FnvBuildHasher 的空 HashMap。
+    /// 迭代顺序只取决于插入的内容和顺序，不受随机种子影响。
+    #[cfg(feature = "alloc")]
+    pub fn fnv() -> Self {
+        Self::with_capacity_and_hasher(INITIAL_CAPACITY, FnvBuildHasher)
+    }
 }
 
 impl<K, V, S> HashMap<K, V, S>
@@ -108,8 +243,16 @@ where
     K: Hash + Eq,
     S: BuildHasher,
 {
+    /// 创建一个默认容量的空 HashMap，使用给定的哈希状态构建器，
+    /// 例如 `AxRandomState::with_seed(..)` 或自定义的 `BuildHasher`。
+    #[cfg(feature = "alloc")]
+    pub fn with_hasher(hasher_builder: S) -> Self {
+        Self::with_capacity_and_hasher(INITIAL_CAPACITY, hasher_builder)
+    }
+
+    /// 创建一个至少能容纳 `capacity` 个桶的 HashMap，使用给定的哈希状态构建器。
     #[cfg(feature = "alloc")]
-    fn with_capacity_and_hasher(capacity: usize, hasher_builder: S) -> Self {
+    pub fn with_capacity_and_hasher(capacity: usize, hasher_builder: S) -> Self {
         let cap = usize::max(INITIAL_CAPACITY, capacity.next_power_of_two());
         let mut buckets = Vec::with_capacity(cap);
         for _ in 0..cap {
@@ -119,6 +262,8 @@ where
             buckets,
             len: 0,
             hasher_builder,
+            load_factor_threshold: LOAD_FACTOR_THRESHOLD,
+            resizes: 0,
         }
     }
 
@@ -136,8 +281,7 @@ where
         if self.buckets.is_empty() { // 防止除以零或对空桶取模
             return 0;
         }
-        // 确保桶的数量是2的幂，这样可以用位运算代替取模
-        (hash & (self.buckets.len() as u64 - 1)) as usize
+        bucket_index_in(hash, self.buckets.len())
     }
 
     fn resize_if_needed(&mut self) {
@@ -152,7 +296,7 @@ where
         }
 
         let load_factor = self.len as f32 / self.buckets.len() as f32;
-        if load_factor > LOAD_FACTOR_THRESHOLD && self.buckets.len() > 0 {
+        if load_factor > self.load_factor_threshold && self.buckets.len() > 0 {
             self.resize();
         }
     }
@@ -168,12 +312,23 @@ where
         if new_capacity == current_capacity { // 如果容量没有变化 (例如已经达到最大或溢出)
             return;
         }
+        self.resize_to(new_capacity);
+    }
 
+    // 把所有元素重新分布到 new_capacity 个桶中，只有扩容计入 resizes
+    fn resize_to(&mut self, new_capacity: usize) {
         let mut new_buckets_vec = Vec::with_capacity(new_capacity);
         for _ in 0..new_capacity {
             new_buckets_vec.push(Bucket::new());
         }
-        
+        self.redistribute(new_buckets_vec);
+    }
+
+    // 把所有元素重新分布到给定的空桶中
+    fn redistribute(&mut self, new_buckets_vec: Vec<Bucket<K, V>>) {
+        if new_buckets_vec.len() > self.buckets.len() {
+            self.resizes += 1;
+        }
         let old_buckets = mem::replace(&mut self.buckets, new_buckets_vec);
         self.len = 0; // 长度将在重新插入时更新
 
@@ -189,43 +344,340 @@ where
         }
     }
     
+    /// 换用新构建的哈希状态构建器，在桶数不变的情况下重新分布所有元素。
+    ///
+    /// 对 `AxRandomState` 来说会换一个随机种子，可以让因碰撞攻击或退化的键集
+    /// 而严重不均的桶重新变得均匀；`FnvBuildHasher` 等固定种子的构建器重建后
+    /// 布局不变。
+    pub fn rehash_all(&mut self)
+    where
+        S: Default,
+    {
+        self.hasher_builder = S::default();
+        self.resize_to(self.buckets.len());
+    }
+
+    /// 与 [`reserve`](Self::reserve) 相同，但容量溢出或内存不足时返回错误，
+    /// 表保持不变，而不是 panic 或中止。
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let new_capacity = self
+            .len
+            .checked_add(additional)
+            .and_then(|needed| ((needed as f32 / self.load_factor_threshold) as usize).checked_add(1))
+            .and_then(usize::checked_next_power_of_two)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if new_capacity > self.buckets.len() {
+            let layout = Layout::array::<Bucket<K, V>>(new_capacity)
+                .map_err(|_| TryReserveError::CapacityOverflow)?;
+            let ptr = unsafe { arceos_api::mem::ax_alloc(layout) }
+                .ok_or(TryReserveError::AllocError { layout })?;
+            // SAFETY: ax_alloc 使用的就是 #[global_allocator]，布局与 Vec 的要求一致，
+            // 之后由 Vec 按同样的布局释放
+            let mut new_buckets_vec =
+                unsafe { Vec::from_raw_parts(ptr.as_ptr().cast(), 0, new_capacity) };
+            new_buckets_vec.resize_with(new_capacity, Bucket::new);
+
+            // 先数出每个新桶要放的元素个数，逐个预留好空间，
+            // 这样 redistribute 搬移元素时不会再分配内存
+            let counts_layout = Layout::array::<usize>(new_capacity)
+                .map_err(|_| TryReserveError::CapacityOverflow)?;
+            let mut counts = Vec::new();
+            counts
+                .try_reserve_exact(new_capacity)
+                .map_err(|_| TryReserveError::AllocError { layout: counts_layout })?;
+            counts.resize(new_capacity, 0usize);
+            for (key, _) in self.buckets.iter().flat_map(|bucket| bucket.items.iter()) {
+                counts[bucket_index_in(self.make_hash(key), new_capacity)] += 1;
+            }
+            for (bucket, &count) in new_buckets_vec.iter_mut().zip(counts.iter()) {
+                let layout = Layout::array::<(K, V)>(count)
+                    .map_err(|_| TryReserveError::CapacityOverflow)?;
+                bucket
+                    .items
+                    .try_reserve_exact(count)
+                    .map_err(|_| TryReserveError::AllocError { layout })?;
+            }
+            self.redistribute(new_buckets_vec);
+        }
+        Ok(())
+    }
+
+    /// 预留空间，使再插入 `additional` 个元素时不需要扩容。
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.saturating_add(additional);
+        let min_buckets = (needed as f32 / self.load_factor_threshold) as usize + 1;
+        let new_capacity = min_buckets.next_power_of_two();
+        if new_capacity > self.buckets.len() {
+            self.resize_to(new_capacity);
+        }
+    }
+
+    /// 缩小桶的数量，回收大量删除后多余的桶。
+    ///
+    /// 新的桶数是不小于 `min_capacity`、且能让负载因子保持在阈值以下的最小的 2 的幂，
+    /// 不会低于初始容量；如果它不比当前桶数小，则什么也不做。
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        let min_buckets = (self.len as f32 / self.load_factor_threshold) as usize + 1;
+        let new_capacity = min_capacity
+            .max(min_buckets)
+            .max(INITIAL_CAPACITY)
+            .next_power_of_two();
+        if new_capacity < self.buckets.len() {
+            self.resize_to(new_capacity);
+        }
+    }
+
+    /// 批量插入键值对，键已存在时更新其值。
+    ///
+    /// 先按迭代器的 `size_hint` 一次性预留空间，插入过程中不再逐个检查阈值：
+    /// 只在负载因子超过阈值的两倍、或某条链的长度达到桶数时扩容，
+    /// 这样 `size_hint` 偏小时总的代价仍是线性的；最后再按阈值统一扩容一次。
+    pub fn insert_many<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (key, value) in iter {
+            if self.len as f32 > self.buckets.len() as f32 * self.load_factor_threshold * 2.0 {
+                self.resize();
+            }
+            self.insert_no_resize(key, value);
+        }
+        self.reserve(0);
+    }
+
     /// 插入一个键值对到 HashMap 中。
     /// 如果键已存在，则更新其值，并返回旧值。否则，返回 `None`。
     pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        // resize_if_needed 之后桶一定不为空
         self.resize_if_needed();
-        
-        let hash = self.make_hash(&key);
-        let index = self.bucket_index(hash);
+        self.insert_no_resize(key, value)
+    }
 
-        // 确保 resize_if_needed 之后 buckets 不会为空
-        if self.buckets.is_empty() {
-             // 这是一个理论上的防护，resize_if_needed 应该已经处理了空桶的情况
-            self.resize_if_needed(); // 再次尝试初始化
-             if self.buckets.is_empty() { // 如果还是空，则无法继续
-                 // 在 no_std 环境下，panic 可能不是最好的选择，但这里为了简单
-                 // 或者可以返回一个错误类型，但这会改变函数签名
-                 panic!("Failed to initialize buckets for HashMap");
-             }
-        }
+    // 一条链最多容纳的元素个数，取当前的桶数
+    fn max_probe(&self) -> usize {
+        self.buckets.len()
+    }
 
+    // 不检查负载因子的插入，调用者负责之后扩容
+    fn insert_no_resize(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.make_hash(&key);
+        let index = self.bucket_index(hash);
+        self.insert_at(index, key, value)
+    }
 
-        let bucket = &mut self.buckets[index];
-        for item in bucket.items.iter_mut() {
+    // 插入到给定的桶中，index 必须是 key 所在的桶。
+    // 键不存在而链已经达到 max_probe 时（哈希器退化，或 insert_many 还没来得及扩容）
+    // 先扩容，而不是让链无限增长；扩容后链长只有新桶数的一半
+    fn insert_at(&mut self, mut index: usize, key: K, value: V) -> Option<V> {
+        for item in self.buckets[index].items.iter_mut() {
             if item.0 == key { // K 必须实现 Eq
                 return Some(mem::replace(&mut item.1, value));
             }
         }
 
-        bucket.items.push((key, value));
+        if self.buckets[index].items.len() >= self.max_probe() {
+            self.resize();
+            index = self.bucket_index(self.make_hash(&key));
+        }
+        self.buckets[index].items.push((key, value));
         self.len += 1;
         None
     }
 
+    /// 查找 `key`，不存在时插入 `default()` 的结果，返回值的可变引用。
+    /// `default` 只在键不存在时才会被调用。
+    pub fn get_or_insert_with<F: FnOnce() -> V>(&mut self, key: K, default: F) -> &mut V {
+        // 先扩容再定位桶，保证返回的引用指向最终的位置
+        self.resize_if_needed();
+
+        let hash = self.make_hash(&key);
+        let mut index = self.bucket_index(hash);
+        // 与 insert_at 一样，键不存在且链已达到探测上限时先扩容
+        if self.buckets[index].items.len() >= self.max_probe()
+            && !self.buckets[index].items.iter().any(|(k, _)| *k == key)
+        {
+            self.resize();
+            index = self.bucket_index(hash);
+        }
+        let items = &mut self.buckets[index].items;
+        let pos = match items.iter().position(|(k, _)| *k == key) {
+            Some(pos) => pos,
+            None => {
+                items.push((key, default()));
+                self.len += 1;
+                items.len() - 1
+            }
+        };
+        &mut items[pos].1
+    }
+
+    /// 用借用的键定位条目，只有在键不存在、真正需要插入时才通过 `K::from` 构造拥有所有权的键。
+    /// 例如 `HashMap<String, _>` 可以直接用 `&str` 计数，已存在的键不会被重复分配。
+    pub fn entry_ref<'q, Q: ?Sized>(&mut self, key: &'q Q) -> EntryRef<'_, 'q, K, Q, V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        // 与 get_or_insert_with 一样，先扩容再定位桶
+        self.resize_if_needed();
+
+        let hash = self.make_hash(key);
+        let mut index = self.bucket_index(hash);
+        let pos = self.buckets[index].items.iter().position(|(k, _)| key.eq(k.borrow()));
+        // 之后可能插入，链已达到探测上限时同样先扩容
+        if pos.is_none() && self.buckets[index].items.len() >= self.max_probe() {
+            self.resize();
+            index = self.bucket_index(hash);
+        }
+        let items = &mut self.buckets[index].items;
+        EntryRef {
+            items,
+            len: &mut self.len,
+            pos,
+            key,
+        }
+    }
+
+    /// 把 `other` 中的所有键值对移动到 `self` 中，键冲突时使用 `other` 的值。
+    /// 之后 `other` 为空，但保留原有的桶。
+    ///
+    /// 两个表的哈希种子不同，每个键都要在 `self` 中重新哈希。
+    pub fn append(&mut self, other: &mut HashMap<K, V, S>) {
+        for bucket in other.buckets.iter_mut() {
+            for (key, value) in bucket.items.drain(..) {
+                self.insert(key, value);
+            }
+        }
+        other.len = 0;
+    }
+
+    /// 移除 `key` 对应的键值对，返回其值。键不存在时返回 `None`。
+    pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    /// 移除 `key` 对应的键值对，连同表中存储的键一起返回。
+    /// 适用于存储的键带有额外信息（例如规范化后的形式）的情况。
+    pub fn remove_entry<Q: ?Sized>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        if self.is_empty() {
+            return None;
+        }
+        let hash = self.make_hash(key);
+        let index = self.bucket_index(hash);
+        let items = &mut self.buckets[index].items;
+        let pos = items.iter().position(|(k, _)| key.eq(k.borrow()))?;
+        self.len -= 1;
+        // get_mut_promote 把热点键留在链头，用 remove 保持链内其余元素的顺序
+        Some(items.remove(pos))
+    }
+
+    /// 返回表中任意一个键值对，表为空时返回 `None`。
+    ///
+    /// 按顺序扫描桶，返回第一个非空桶中的元素；最坏情况下是 O(容量)，
+    /// 但通常很快就能找到。适用于不需要完整 LRU 的随机淘汰。
+    pub fn any_entry(&self) -> Option<(&K, &V)> {
+        if self.is_empty() {
+            return None;
+        }
+        self.buckets
+            .iter()
+            .find_map(|bucket| bucket.items.last())
+            .map(|(k, v)| (k, v))
+    }
+
+    /// 移除并返回表中任意一个键值对，表为空时返回 `None`。
+    ///
+    /// 移除的就是 [`any_entry`](Self::any_entry) 返回的那一个。
+    pub fn pop_any(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            return None;
+        }
+        let item = self.buckets.iter_mut().find_map(|bucket| bucket.items.pop())?;
+        self.len -= 1;
+        Some(item)
+    }
+
+    /// 返回一个迭代器，依次移除并返回 `f` 返回 `true` 的键值对。
+    /// 其余的键值对保留在表中；迭代器提前丢弃时，尚未访问的键值对也会保留。
+    pub fn extract_if<F>(&mut self, f: F) -> ExtractIf<'_, K, V, S, F>
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        ExtractIf {
+            map: self,
+            pred: f,
+            bucket_idx: 0,
+            item_idx: 0,
+        }
+    }
+
+    /// 只保留 `f` 返回 `true` 的键对应的键值对。
+    pub fn retain_keys<F: FnMut(&K) -> bool>(&mut self, mut f: F) {
+        for bucket in self.buckets.iter_mut() {
+            bucket.items.retain(|(k, _)| f(k));
+        }
+        self.len = self.buckets.iter().map(|b| b.items.len()).sum();
+    }
+
+    /// 消耗 HashMap，用 `f` 转换所有的值，得到键相同的新表。
+    ///
+    /// 哈希值只取决于键，所以直接沿用原来的桶布局和哈希状态构建器，不需要重新哈希。
+    pub fn map_values<W, F: FnMut(V) -> W>(self, mut f: F) -> HashMap<K, W, S> {
+        let buckets = self
+            .buckets
+            .into_iter()
+            .map(|bucket| Bucket {
+                items: bucket.items.into_iter().map(|(k, v)| (k, f(v))).collect(),
+            })
+            .collect();
+        HashMap {
+            buckets,
+            len: self.len,
+            hasher_builder: self.hasher_builder,
+            load_factor_threshold: self.load_factor_threshold,
+            resizes: self.resizes,
+        }
+    }
+
     /// 返回一个迭代器，用于遍历 HashMap 中的所有键值对。
     pub fn iter(&self) -> Iter<'_, K, V, S> {
         Iter::new(self)
     }
 
+    /// 返回按键升序排列的所有键值对。
+    ///
+    /// `iter` 的顺序取决于随机种子和桶布局，内容相同的表也可能不同；
+    /// 排序后的结果与这些无关，便于打印日志和比较快照。
+    pub fn iter_sorted(&self) -> Vec<(&K, &V)>
+    where
+        K: Ord,
+    {
+        let mut items: Vec<_> = self.iter().collect();
+        items.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        items
+    }
+
+    /// 消耗 HashMap，返回依次产生所有键的迭代器。
+    pub fn into_keys(self) -> IntoKeys<K, V> {
+        IntoKeys {
+            inner: IntoPairs::new(self.buckets, self.len),
+        }
+    }
+
+    /// 消耗 HashMap，返回依次产生所有值的迭代器。
+    pub fn into_values(self) -> IntoValues<K, V> {
+        IntoValues {
+            inner: IntoPairs::new(self.buckets, self.len),
+        }
+    }
+
     // 为完整性添加 get, len, is_empty (实验可能不直接测试这些，但好的 HashMap 应该有)
     pub fn get<Q: ?Sized>(&self, key: &Q) -> Option<&V>
     where
@@ -243,21 +695,179 @@ where
         }
         None
     }
-    
+
+    /// 查找 `key` 并把找到的条目移到所在链的最前面（move-to-front），返回值的可变引用。
+    ///
+    /// 反复查找的热点键会停留在链头，之后的查找只需比较一次。代价是每次命中都要把
+    /// 它前面的元素后移一位，与查找时已经做过的比较次数同阶，但需要 `&mut self`；
+    /// 同一条链上交替查找多个键时它们会互相挤占，反而没有收益。桶的数量随负载因子
+    /// 增长，正常情况下链都很短，只有哈希分布不均时才值得使用。
+    /// 只影响链内的顺序（包括迭代顺序），不影响 [`get`](Self::get)。
+    pub fn get_mut_promote<Q: ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        let hash = self.make_hash(key);
+        let index = self.bucket_index(hash);
+        let items = &mut self.buckets[index].items;
+        let pos = items.iter().position(|(k, _)| key.eq(k.borrow()))?;
+        items[..=pos].rotate_right(1);
+        Some(&mut items[0].1)
+    }
+
+    /// 同时返回多个键对应的值的可变引用。
+    ///
+    /// 任意一个键不存在，或者有两个键相同（可变引用会重叠）时返回 `None`。
+    pub fn get_many_mut<Q: ?Sized, const N: usize>(&mut self, keys: [&Q; N]) -> Option<[&mut V; N]>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        // 先找出每个键所在的 (桶下标, 桶内下标)
+        let mut locs = [(0, 0); N];
+        for (loc, key) in locs.iter_mut().zip(keys) {
+            if self.is_empty() {
+                return None;
+            }
+            let index = self.bucket_index(self.make_hash(key));
+            let pos = self.buckets[index]
+                .items
+                .iter()
+                .position(|(k, _)| key.eq(k.borrow()))?;
+            *loc = (index, pos);
+        }
+        for i in 0..N {
+            if locs[..i].contains(&locs[i]) {
+                return None;
+            }
+        }
+        let buckets = self.buckets.as_mut_ptr();
+        // SAFETY: 每个位置都在各自的桶内，并且两两不同，所以得到的可变引用不会重叠；
+        // 它们的生命周期与 `&mut self` 绑定，期间表不会被修改。
+        Some(locs.map(|(index, pos)| unsafe {
+            let items = (*buckets.add(index)).items.as_mut_ptr();
+            &mut (*items.add(pos)).1
+        }))
+    }
+
     pub fn len(&self) -> usize {
         self.len
     }
 
+    /// 返回表使用的哈希状态构建器，可以用它构建哈希行为一致的另一个表。
+    pub fn hasher(&self) -> &S {
+        &self.hasher_builder
+    }
+
+    /// 返回当前桶的数量。
+    pub fn capacity(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// 依次返回每个桶中元素的个数，用于观察哈希分布是否均匀。
+    pub fn bucket_lengths(&self) -> impl Iterator<Item = usize> + '_ {
+        self.buckets.iter().map(|b| b.items.len())
+    }
+
+    /// 返回最长的桶的长度，即一次查找最多需要比较的次数。
+    pub fn max_bucket_len(&self) -> usize {
+        self.bucket_lengths().max().unwrap_or(0)
+    }
+
+    /// 返回表创建以来扩容的次数。
+    pub fn resize_count(&self) -> usize {
+        self.resizes
+    }
+
+    /// 返回当前的负载因子，即元素个数 / 桶数。
+    pub fn load_factor(&self) -> f32 {
+        if self.buckets.is_empty() {
+            return 0.0;
+        }
+        self.len as f32 / self.buckets.len() as f32
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
 }
 
+/// 支持 `map[&key]` 形式的查找，键不存在时 panic（与 std 一致）。
+impl<K, Q: ?Sized, V, S> Index<&Q> for HashMap<K, V, S>
+where
+    K: Eq + Hash + Borrow<Q>,
+    Q: Eq + Hash,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+/// 使用 `S::default()` 创建空表。默认的 `AxRandomState` 也走这里，等价于 `HashMap::new()`。
+#[cfg(feature = "alloc")]
+impl<K, V, S> Default for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_capacity_and_hasher(INITIAL_CAPACITY, S::default())
+    }
+}
+
+/// 克隆时连同哈希状态构建器一起复制，桶的布局不变，不需要重新哈希。
+impl<K: Clone, V: Clone, S: Clone> Clone for HashMap<K, V, S> {
+    fn clone(&self) -> Self {
+        HashMap {
+            buckets: self.buckets.clone(),
+            len: self.len,
+            hasher_builder: self.hasher_builder.clone(),
+            load_factor_threshold: self.load_factor_threshold,
+            resizes: self.resizes,
+        }
+    }
+
+    /// 桶数相同时逐个桶覆盖，复用已有的桶和链的空间；否则重新克隆一份。
+    fn clone_from(&mut self, source: &Self) {
+        if self.buckets.len() != source.buckets.len() {
+            *self = source.clone();
+            return;
+        }
+        for (dst, src) in self.buckets.iter_mut().zip(&source.buckets) {
+            dst.clone_from(src);
+        }
+        self.len = source.len;
+        self.hasher_builder.clone_from(&source.hasher_builder);
+        self.load_factor_threshold = source.load_factor_threshold;
+        self.resizes = source.resizes;
+    }
+}
+
+/// 用数组构造 HashMap，如 `HashMap::from([("a", 1), ("b", 2)])`，重复的键保留最后一个值。
+#[cfg(feature = "alloc")]
+impl<K, V, const N: usize> From<[(K, V); N]> for HashMap<K, V, AxRandomState>
+where
+    K: Hash + Eq,
+{
+    fn from(arr: [(K, V); N]) -> Self {
+        // 按 N 个元素一次分配足够的桶，插入时不再扩容
+        let capacity = (N as f32 / LOAD_FACTOR_THRESHOLD) as usize + 1;
+        let mut map = Self::with_capacity_and_hasher(capacity, AxRandomState::new());
+        map.insert_many(arr);
+        map
+    }
+}
+
 // --- Iter 实现 ---
 pub struct Iter<'a, K: 'a, V: 'a, S: BuildHasher + 'a> {
     map_buckets: &'a Vec<Bucket<K, V>>,
     current_bucket_idx: usize,
     current_item_idx_in_bucket: usize,
+    remaining: usize, // 尚未返回的元素个数
     _hasher_builder_marker: core::marker::PhantomData<&'a S>,
 }
 
@@ -267,6 +877,7 @@ impl<'a, K, V, S: BuildHasher> Iter<'a, K, V, S> {
             map_buckets: &map.buckets,
             current_bucket_idx: 0,
             current_item_idx_in_bucket: 0,
+            remaining: map.len,
             _hasher_builder_marker: core::marker::PhantomData,
         }
     }
@@ -291,6 +902,7 @@ where
             if self.current_item_idx_in_bucket < current_bucket_items.len() {
                 let (key, value) = &current_bucket_items[self.current_item_idx_in_bucket];
                 self.current_item_idx_in_bucket += 1;
+                self.remaining -= 1;
                 return Some((key, value));
             } else {
                 self.current_bucket_idx += 1;
@@ -298,4 +910,186 @@ where
             }
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K, V, S: BuildHasher> ExactSizeIterator for Iter<'a, K, V, S> {
+    fn len(&self) -> usize {
+        self.remaining
+    }
+}
+
+// 迭代器耗尽后 current_bucket_idx 不会再回退，之后总是返回 None
+impl<'a, K, V, S: BuildHasher> FusedIterator for Iter<'a, K, V, S> {}
+
+// --- IntoKeys / IntoValues 实现 ---
+// 逐个桶把元素移出，不复制键和值
+struct IntoPairs<K, V> {
+    buckets: alloc::vec::IntoIter<Bucket<K, V>>,
+    items: alloc::vec::IntoIter<(K, V)>,
+    remaining: usize,
+}
+
+impl<K, V> IntoPairs<K, V> {
+    fn new(buckets: Vec<Bucket<K, V>>, len: usize) -> Self {
+        IntoPairs {
+            buckets: buckets.into_iter(),
+            items: Vec::new().into_iter(),
+            remaining: len,
+        }
+    }
+}
+
+impl<K, V> Iterator for IntoPairs<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                self.remaining -= 1;
+                return Some(item);
+            }
+            self.items = self.buckets.next()?.items.into_iter();
+        }
+    }
+}
+
+/// `HashMap::into_keys` 返回的迭代器。
+pub struct IntoKeys<K, V> {
+    inner: IntoPairs<K, V>,
+}
+
+impl<K, V> Iterator for IntoKeys<K, V> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.inner.remaining, Some(self.inner.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoKeys<K, V> {}
+
+impl<K, V> FusedIterator for IntoKeys<K, V> {}
+
+/// `HashMap::into_values` 返回的迭代器。
+pub struct IntoValues<K, V> {
+    inner: IntoPairs<K, V>,
+}
+
+impl<K, V> Iterator for IntoValues<K, V> {
+    type Item = V;
+
+    fn next(&mut self) -> Option<V> {
+        self.inner.next().map(|(_, v)| v)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.inner.remaining, Some(self.inner.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoValues<K, V> {}
+
+impl<K, V> FusedIterator for IntoValues<K, V> {}
+
+// --- ExtractIf 实现 ---
+pub struct ExtractIf<'a, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    map: &'a mut HashMap<K, V, S>,
+    pred: F,
+    bucket_idx: usize,
+    item_idx: usize,
+}
+
+impl<'a, K, V, S, F> Iterator for ExtractIf<'a, K, V, S, F>
+where
+    F: FnMut(&K, &mut V) -> bool,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.bucket_idx < self.map.buckets.len() {
+            let items = &mut self.map.buckets[self.bucket_idx].items;
+            while self.item_idx < items.len() {
+                let (key, value) = &mut items[self.item_idx];
+                if (self.pred)(key, value) {
+                    // 与 remove_entry 一样保持链内顺序，后面的元素前移一位，
+                    // 所以 item_idx 不用前进
+                    self.map.len -= 1;
+                    return Some(items.remove(self.item_idx));
+                }
+                self.item_idx += 1;
+            }
+            self.bucket_idx += 1;
+            self.item_idx = 0;
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.map.len))
+    }
+}
+
+// --- EntryRef 实现 ---
+/// `HashMap::entry_ref` 返回的条目，`pos` 为 `None` 表示键尚不存在。
+pub struct EntryRef<'a, 'q, K, Q: ?Sized, V> {
+    items: &'a mut Vec<(K, V)>,
+    len: &'a mut usize,
+    pos: Option<usize>,
+    key: &'q Q,
+}
+
+impl<'a, 'q, K, Q: ?Sized, V> EntryRef<'a, 'q, K, Q, V> {
+    /// 返回用于查找的借用键。
+    pub fn key(&self) -> &'q Q {
+        self.key
+    }
+
+    /// 键存在时返回 `true`。
+    pub fn is_occupied(&self) -> bool {
+        self.pos.is_some()
+    }
+
+    /// 键不存在时插入 `default()` 的结果，返回值的可变引用。
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V
+    where
+        K: From<&'q Q>,
+    {
+        let pos = match self.pos {
+            Some(pos) => pos,
+            None => {
+                self.items.push((K::from(self.key), default()));
+                *self.len += 1;
+                self.items.len() - 1
+            }
+        };
+        &mut self.items[pos].1
+    }
+
+    /// 键不存在时插入 `default`，返回值的可变引用。
+    pub fn or_insert(self, default: V) -> &'a mut V
+    where
+        K: From<&'q Q>,
+    {
+        self.or_insert_with(|| default)
+    }
+
+    /// 键不存在时插入 `V::default()`，返回值的可变引用。
+    pub fn or_default(self) -> &'a mut V
+    where
+        K: From<&'q Q>,
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
 }
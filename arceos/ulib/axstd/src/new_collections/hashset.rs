@@ -0,0 +1,108 @@
+//! 基于 HashMap 的简单集合。
+
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::iter::FusedIterator;
+
+use super::hashmap::{AxRandomState, HashMap, Iter};
+
+/// 以 `HashMap<K, ()>` 实现的集合。
+///
+/// 值类型 `()` 不占空间，桶里的 `Vec<(K, ())>` 与 `Vec<K>` 布局相同，
+/// 没有额外的内存开销。
+pub struct HashSet<K, S = AxRandomState> {
+    map: HashMap<K, (), S>,
+}
+
+impl<K: Hash + Eq> HashSet<K, AxRandomState> {
+    /// 创建一个新的、空的集合。
+    pub fn new() -> Self {
+        Self { map: HashMap::new() }
+    }
+}
+
+impl<K, S> HashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// 创建一个使用给定哈希状态构建器的空集合。
+    pub fn with_hasher(hasher_builder: S) -> Self {
+        Self {
+            map: HashMap::with_hasher(hasher_builder),
+        }
+    }
+
+    /// 插入一个元素。元素原本不存在时返回 `true`。
+    pub fn insert(&mut self, key: K) -> bool {
+        self.map.insert(key, ()).is_none()
+    }
+
+    /// 检查集合中是否包含该元素。
+    pub fn contains<Q: ?Sized>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        self.map.get(key).is_some()
+    }
+
+    /// 删除一个元素。元素存在时返回 `true`。
+    pub fn remove<Q: ?Sized>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq,
+    {
+        self.map.remove(key).is_some()
+    }
+
+    /// 返回遍历所有元素的迭代器，顺序不确定。
+    pub fn iter(&self) -> SetIter<'_, K, S> {
+        SetIter {
+            inner: self.map.iter(),
+        }
+    }
+
+    /// 返回元素的个数。
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// 检查集合是否为空。
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K, S> Default for HashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self {
+            map: HashMap::default(),
+        }
+    }
+}
+
+/// `HashSet::iter` 返回的迭代器。
+pub struct SetIter<'a, K, S: BuildHasher> {
+    inner: Iter<'a, K, (), S>,
+}
+
+impl<'a, K, S: BuildHasher> Iterator for SetIter<'a, K, S> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        self.inner.next().map(|(k, _)| k)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a, K, S: BuildHasher> ExactSizeIterator for SetIter<'a, K, S> {}
+
+impl<'a, K, S: BuildHasher> FusedIterator for SetIter<'a, K, S> {}
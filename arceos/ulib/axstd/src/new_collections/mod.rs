@@ -8,7 +8,34 @@ pub mod hashmap;
 #[cfg(feature = "alloc")]
 pub use self::hashmap::HashMap;
 
-// 如果你的 AxRandomState 需要在外部被直接使用（例如，如果用户想用 HashMap::with_hasher(AxRandomState)），
-// 你也可以在这里导出它。对于本实验，可能不需要。
-// #[cfg(feature = "alloc")]
-// pub use self::hash_map::AxRandomState;
\ No newline at end of file
+// 导出 AxRandomState，便于用 HashMap::with_capacity_and_hasher 指定固定种子。
+#[cfg(feature = "alloc")]
+pub use self::hashmap::AxRandomState;
+
+// 导出 RandomSource，用于替换 AxRandomState 的随机源。
+#[cfg(feature = "alloc")]
+pub use self::hashmap::RandomSource;
+
+// 导出 FnvBuildHasher，用于不需要随机性、只需要确定性的表。
+#[cfg(feature = "alloc")]
+pub use self::hashmap::FnvBuildHasher;
+
+// 导出 EntryRef，便于在函数签名中使用 HashMap::entry_ref 的返回类型。
+#[cfg(feature = "alloc")]
+pub use self::hashmap::EntryRef;
+
+// 导出 TryReserveError，即 HashMap::try_reserve 的错误类型。
+#[cfg(feature = "alloc")]
+pub use self::hashmap::TryReserveError;
+
+// 导出 IntoKeys / IntoValues，即 HashMap::into_keys / into_values 的返回类型。
+#[cfg(feature = "alloc")]
+pub use self::hashmap::{IntoKeys, IntoValues};
+
+// 声明 hashset 子模块，基于 HashMap<K, ()> 的集合。
+#[cfg(feature = "alloc")]
+pub mod hashset;
+
+// 导出 HashSet，用法与 HashMap 类似，但只保存键。
+#[cfg(feature = "alloc")]
+pub use self::hashset::HashSet;